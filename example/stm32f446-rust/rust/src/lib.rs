@@ -6,7 +6,7 @@ extern crate rttrust;
 use rttrust::{
     ipc::{mutex::Mutex, IpcFlag},
     object::Object,
-    thread::Thread,
+    thread::{Thread, ThreadContext},
     Box,
 };
 
@@ -29,18 +29,19 @@ pub extern "C" fn rust_main() {
         {
             let mutex = mutex.clone();
             move || {
+                let ctx = ThreadContext::current().unwrap();
                 println!("Rust Thread test");
 
                 println!("Mutex in closure: {:?}", mutex);
 
                 mutex.take(0).expect("take test failed");
 
-                Thread::delay(1000).unwrap();
+                Thread::delay(1000, ctx).unwrap();
 
                 mutex.release().expect("release test failed");
 
                 loop {
-                    Thread::delay(5000).ok();
+                    Thread::delay(5000, ctx).ok();
                 }
             }
         },
@@ -52,7 +53,8 @@ pub extern "C" fn rust_main() {
     .startup()
     .expect("Thread startup failed");
 
-    Thread::delay(100).unwrap();
+    let ctx = ThreadContext::current().expect("rust_main runs in the main thread");
+    Thread::delay(100, ctx).unwrap();
 
     println!("waiting mutex release");
 
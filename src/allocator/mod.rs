@@ -0,0 +1,141 @@
+//! RT-Thread allows static memory pool management and dynamic memory heap management. 
+//! 
+//! When static memory pool has available memory, the time allocated to the memory block will be constant; when the static memory pool is empty, the system will then request for suspending or blocking the thread of the memory block. (that is, the thread will abandon the request and return, if after waiting for a while, the memory block is not obtained or the thread will abandon and return immediately. The waiting time depends on the waiting time parameter set when the memory block is applied). When other threads release the memory block to the memory pool, if there is threads that are suspending and waiting to be allocated of memory blocks, the system will wake up the thread.
+//! 
+//! Under circumstances of different system resources, the dynamic memory heap management module respectively provides memory management algorithms for small memory systems and SLAB memory management algorithm for large memory systems.
+//! 
+//! There is also a dynamic memory heap management called memheap, which is suitable for memory heaps in systems with multiple addresses that can be discontinuous. Using memheap, you can "stick" multiple memory heaps together, letting the user operate as if he was operating a memory heap .
+
+use crate::{
+    ffi::{
+        rt_calloc, rt_free, rt_free_align, rt_malloc, rt_malloc_align, rt_memory_info,
+        rt_realloc, rt_size_t, RT_ALIGN_SIZE,
+    },
+    ipc::SpinLock,
+};
+use core::alloc::{GlobalAlloc, Layout};
+
+#[cfg(feature = "alloc-tracking")]
+pub mod tracking;
+
+cfg_if! {
+    if #[cfg(feature = "alloc-tracking")] {
+        #[global_allocator]
+        static ALLOCATOR: tracking::TrackingAllocator<SystemHeapAllocator> =
+            tracking::TrackingAllocator::new(SystemHeapAllocator);
+    } else {
+        #[global_allocator]
+        static ALLOCATOR: SystemHeapAllocator = SystemHeapAllocator;
+    }
+}
+
+/// Global allocator on the system heap
+///
+/// Layouts whose alignment is guaranteed by the heap (`RT_ALIGN_SIZE`) go through
+/// `rt_malloc`, `rt_calloc` and `rt_realloc`. Higher alignments use `rt_malloc_align`.
+pub struct SystemHeapAllocator;
+
+#[inline]
+fn is_natural(layout: &Layout) -> bool {
+    layout.align() <= RT_ALIGN_SIZE as usize
+}
+
+unsafe impl GlobalAlloc for SystemHeapAllocator {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if is_natural(&layout) {
+            rt_malloc(layout.size() as rt_size_t).cast()
+        } else {
+            rt_malloc_align(layout.size() as rt_size_t, layout.align() as rt_size_t).cast()
+        }
+    }
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if is_natural(&layout) {
+            rt_free(ptr.cast())
+        } else {
+            rt_free_align(ptr.cast())
+        }
+    }
+    #[inline]
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if is_natural(&layout) {
+            rt_calloc(1, layout.size() as rt_size_t).cast()
+        } else {
+            let ptr = self.alloc(layout);
+            if !ptr.is_null() {
+                ptr.write_bytes(0, layout.size());
+            }
+            ptr
+        }
+    }
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if is_natural(&layout) {
+            return rt_realloc(ptr.cast(), new_size as rt_size_t).cast();
+        }
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+/// Hook called when an allocation fails and the failure cannot be handled
+pub type OomHook = fn(Layout);
+
+static OOM_HOOK: SpinLock<Option<OomHook>> = SpinLock::new(None);
+
+/// Set the hook called by the allocation error handler, for example to log the heap
+/// usage or to reboot the board
+///
+/// If the hook returns, the handler panics.
+pub fn set_oom_hook(hook: OomHook) {
+    *OOM_HOOK.lock() = Some(hook);
+}
+
+/// Remove the hook set by [set_oom_hook](set_oom_hook)
+pub fn take_oom_hook() -> Option<OomHook> {
+    OOM_HOOK.lock().take()
+}
+
+pub(crate) fn handle_alloc_error(layout: Layout) -> ! {
+    let hook = *OOM_HOOK.lock();
+    if let Some(hook) = hook {
+        hook(layout);
+    }
+    panic!("allocation error: {:?}, {:?}", layout, memory_info())
+}
+
+/// Usage of the system heap in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapInfo {
+    pub total: usize,
+    pub used: usize,
+    /// Peak of `used` since the system started
+    pub max_used: usize,
+}
+
+impl HeapInfo {
+    #[inline]
+    pub fn free(&self) -> usize {
+        self.total - self.used
+    }
+}
+
+///
+/// This function will return the usage of the system heap.
+///
+#[inline]
+pub fn memory_info() -> HeapInfo {
+    let (mut total, mut used, mut max_used) = (0, 0, 0);
+    unsafe { rt_memory_info(&mut total, &mut used, &mut max_used) };
+    HeapInfo {
+        total: total as usize,
+        used: used as usize,
+        max_used: max_used as usize,
+    }
+}
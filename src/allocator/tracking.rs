@@ -0,0 +1,277 @@
+//! Allocation tracking of the global allocator, enabled by the `alloc-tracking` feature
+//!
+//! Every allocation carries a small header recording the thread which allocated it, so
+//! the live allocations can be accounted to that thread even if they are freed by
+//! another one. [report](report) dumps the statistics to the console.
+//!
+//! At most `TRACK_SLOTS` threads are tracked separately, allocations of other threads
+//! and of interrupt handlers are accounted as untracked. Allocations made before the
+//! scheduler starts are accounted to the thread with an empty name.
+//!
+//! Threads are told apart by their object, which a new thread may reuse once the old
+//! one is freed. Threads created by this crate are forgotten when they are cleaned up,
+//! the allocations of other threads sharing an object are accounted together.
+
+use super::HeapInfo;
+use crate::{
+    ffi::RT_NAME_MAX,
+    fmt::Console,
+    ipc::SpinLock,
+    isr::in_isr,
+    object::Object,
+    thread::Thread,
+};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    fmt::Write,
+    mem::{align_of, size_of},
+};
+
+/// Number of threads tracked separately
+pub const TRACK_SLOTS: usize = 32;
+
+/// Id of the allocations accounted as untracked
+const UNTRACKED: usize = 0;
+
+/// Live allocations of one thread
+#[derive(Debug, Clone, Copy)]
+pub struct ThreadUsage {
+    thread: usize,
+    /// Stored in the header of the allocations, unique to one thread
+    id: usize,
+    /// Cleared once the thread is cleaned up, its object may then be reused
+    alive: bool,
+    name: [u8; RT_NAME_MAX as usize],
+    /// Number of live allocations
+    pub count: usize,
+    /// Bytes requested by the live allocations
+    pub bytes: usize,
+}
+
+impl ThreadUsage {
+    const EMPTY: Self = Self {
+        thread: 0,
+        id: UNTRACKED,
+        alive: false,
+        name: [0; RT_NAME_MAX as usize],
+        count: 0,
+        bytes: 0,
+    };
+
+    /// Name of the thread when it first allocated
+    pub fn name(&self) -> &str {
+        let len = self.name.iter().position(|&c| c == 0).unwrap_or(self.name.len());
+        core::str::from_utf8(&self.name[..len]).unwrap_or("?")
+    }
+
+    #[inline]
+    fn is_free(&self) -> bool {
+        self.count == 0
+    }
+}
+
+struct Table {
+    slots: [ThreadUsage; TRACK_SLOTS],
+    untracked: ThreadUsage,
+    next_id: usize,
+}
+
+impl Table {
+    /// Account an allocation of `thread`, `None` in interrupt context, and return the
+    /// id to store in its header
+    fn add(&mut self, thread: Option<usize>, size: usize) -> usize {
+        let slot = match thread.and_then(|thread| self.find_or_insert(thread)) {
+            Some(index) => &mut self.slots[index],
+            None => &mut self.untracked,
+        };
+        slot.count += 1;
+        slot.bytes += size;
+        slot.id
+    }
+
+    fn find_or_insert(&mut self, thread: usize) -> Option<usize> {
+        if let Some(index) = self
+            .slots
+            .iter()
+            .position(|s| !s.is_free() && s.alive && s.thread == thread)
+        {
+            return Some(index);
+        }
+        let index = self.slots.iter().position(ThreadUsage::is_free)?;
+        let slot = &mut self.slots[index];
+        *slot = ThreadUsage::EMPTY;
+        slot.thread = thread;
+        slot.alive = true;
+        slot.id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(UNTRACKED + 1);
+        if let Ok(current) = Thread::current() {
+            let name = current.get_name();
+            let name: &str = name.as_ref();
+            slot.name.copy_from_slice(&name.as_bytes()[..slot.name.len()]);
+        }
+        Some(index)
+    }
+
+    fn find(&mut self, id: usize) -> &mut ThreadUsage {
+        match self.slots.iter().position(|s| !s.is_free() && s.id == id) {
+            Some(index) => &mut self.slots[index],
+            None => &mut self.untracked,
+        }
+    }
+
+    fn resize(&mut self, id: usize, old_size: usize, new_size: usize) {
+        let slot = self.find(id);
+        slot.bytes = (slot.bytes + new_size).saturating_sub(old_size);
+    }
+
+    fn remove(&mut self, id: usize, size: usize) {
+        let slot = self.find(id);
+        slot.count = slot.count.saturating_sub(1);
+        slot.bytes = slot.bytes.saturating_sub(size);
+    }
+
+    /// Keep the live allocations of an exited thread apart from a new thread reusing
+    /// its object
+    fn retire(&mut self, thread: usize) {
+        for slot in self
+            .slots
+            .iter_mut()
+            .filter(|s| s.alive && s.thread == thread)
+        {
+            slot.alive = false;
+        }
+    }
+}
+
+/// Global allocator wrapper recording the live allocations of each thread
+pub struct TrackingAllocator<A> {
+    inner: A,
+    table: SpinLock<Table>,
+}
+
+unsafe impl<A: Sync> Sync for TrackingAllocator<A> {}
+
+/// Offset of the user data from the real allocation, the id of the owner thread is
+/// stored right before the user data
+#[inline]
+fn header_layout(layout: Layout) -> Option<(Layout, usize)> {
+    let offset = layout.align().max(size_of::<usize>());
+    let size = layout.size().checked_add(offset)?;
+    let align = layout.align().max(align_of::<usize>());
+    Layout::from_size_align(size, align).ok().map(|l| (l, offset))
+}
+
+/// `None` in interrupt context, the interrupted thread is not charged
+#[inline]
+fn current_thread() -> Option<usize> {
+    if in_isr() {
+        None
+    } else {
+        Some(Thread::current().map_or(0, |thread| thread.get_ptr().as_ptr() as usize))
+    }
+}
+
+/// Forget a thread which has been cleaned up, called from its cleanup hook
+pub(crate) fn thread_exited(thread: usize) {
+    super::ALLOCATOR.table.lock().retire(thread);
+}
+
+impl<A> TrackingAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        Self {
+            inner,
+            table: SpinLock::new(Table {
+                slots: [ThreadUsage::EMPTY; TRACK_SLOTS],
+                untracked: ThreadUsage::EMPTY,
+                next_id: UNTRACKED + 1,
+            }),
+        }
+    }
+
+    /// Copy the usage of all the tracked threads and the untracked allocations
+    pub fn usage(&self) -> ([ThreadUsage; TRACK_SLOTS], ThreadUsage) {
+        let table = self.table.lock();
+        (table.slots, table.untracked)
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let (real, offset) = match header_layout(layout) {
+            Some(real) => real,
+            None => return core::ptr::null_mut(),
+        };
+        let raw = self.inner.alloc(real);
+        if raw.is_null() {
+            return raw;
+        }
+        let ptr = raw.add(offset);
+        let id = self.table.lock().add(current_thread(), layout.size());
+        ptr.cast::<usize>().sub(1).write(id);
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let (real, offset) = match header_layout(layout) {
+            Some(real) => real,
+            None => return core::ptr::null_mut(),
+        };
+        let raw = self.inner.alloc_zeroed(real);
+        if raw.is_null() {
+            return raw;
+        }
+        let ptr = raw.add(offset);
+        let id = self.table.lock().add(current_thread(), layout.size());
+        ptr.cast::<usize>().sub(1).write(id);
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let (real, offset) = match header_layout(layout) {
+            Some(real) => real,
+            None => return core::ptr::null_mut(),
+        };
+        // The offset only depends on the alignment, so the header stays in place
+        let id = ptr.cast::<usize>().sub(1).read();
+        let raw = self.inner.realloc(ptr.sub(offset), real, new_size + offset);
+        if raw.is_null() {
+            return raw;
+        }
+        self.table.lock().resize(id, layout.size(), new_size);
+        raw.add(offset)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let (real, offset) = match header_layout(layout) {
+            Some(real) => real,
+            None => return,
+        };
+        let id = ptr.cast::<usize>().sub(1).read();
+        self.table.lock().remove(id, layout.size());
+        self.inner.dealloc(ptr.sub(offset), real)
+    }
+}
+
+/// Print the heap usage and the live allocations of each thread to the console
+pub fn report() {
+    let mut writer = Console {};
+    let HeapInfo {
+        total,
+        used,
+        max_used,
+    } = super::memory_info();
+    writeln!(writer, "heap total {} used {} max used {}", total, used, max_used).ok();
+    writeln!(writer, "{:<8} {:>8} {:>10}", "thread", "count", "bytes").ok();
+    let (slots, untracked) = super::ALLOCATOR.usage();
+    for slot in slots.iter().filter(|s| !s.is_free()) {
+        writeln!(writer, "{:<8} {:>8} {:>10}", slot.name(), slot.count, slot.bytes).ok();
+    }
+    if !untracked.is_free() {
+        writeln!(
+            writer,
+            "{:<8} {:>8} {:>10}",
+            "<other>", untracked.count, untracked.bytes
+        )
+        .ok();
+    }
+}
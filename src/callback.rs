@@ -1,4 +1,5 @@
 use crate::Box;
+use core::sync::atomic::{AtomicU8, Ordering};
 use cty::c_void;
 
 pub unsafe extern "C" fn callback_entry(parameter: *mut c_void) {
@@ -6,6 +7,77 @@ pub unsafe extern "C" fn callback_entry(parameter: *mut c_void) {
     closure();
 }
 
+/// Closure of a repeatable callback, see [RepeatCallback](RepeatCallback)
+struct Repeat {
+    closure: Box<dyn FnMut() + Send + 'static>,
+    /// One of `REPEAT_IDLE`, `REPEAT_RUNNING` or `REPEAT_RELEASED`
+    state: AtomicU8,
+}
+
+const REPEAT_IDLE: u8 = 0;
+const REPEAT_RUNNING: u8 = 1;
+/// Released while running, the entry drops the closure once it returns
+const REPEAT_RELEASED: u8 = 2;
+
+/// Entry of callbacks packed by
+/// [RepeatCallback::into_repeat_parameter](RepeatCallback::into_repeat_parameter),
+/// the closure is kept alive between calls
+///
+/// ## Safety
+///
+/// `parameter` must come from
+/// [RepeatCallback::into_repeat_parameter](RepeatCallback::into_repeat_parameter) and
+/// must not have been freed by [release_repeat_callback](release_repeat_callback).
+pub unsafe extern "C" fn repeat_entry(parameter: *mut c_void) {
+    let repeat: *mut Repeat = parameter.cast();
+    if (*repeat)
+        .state
+        .compare_exchange(
+            REPEAT_IDLE,
+            REPEAT_RUNNING,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        )
+        .is_err()
+    {
+        // Already released, the closure must not run again
+        return;
+    }
+    ((*repeat).closure)();
+    // Both sides decide with a single atomic operation, so exactly one of them frees
+    // the closure and neither touches it afterwards
+    if (*repeat)
+        .state
+        .compare_exchange(
+            REPEAT_RUNNING,
+            REPEAT_IDLE,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        )
+        .is_err()
+    {
+        // Released while running, e.g. the timer was deleted by the closure itself
+        drop(Box::from_raw(repeat));
+    }
+}
+
+/// Drop a closure packed by
+/// [RepeatCallback::into_repeat_parameter](RepeatCallback::into_repeat_parameter)
+///
+/// If the closure is running, it is dropped once it returns.
+///
+/// ## Safety
+///
+/// `parameter` must come from
+/// [RepeatCallback::into_repeat_parameter](RepeatCallback::into_repeat_parameter) and
+/// [repeat_entry](repeat_entry) must not be called with it afterwards.
+pub unsafe fn release_repeat_callback(parameter: *mut c_void) {
+    let repeat: *mut Repeat = parameter.cast();
+    if (*repeat).state.swap(REPEAT_RELEASED, Ordering::AcqRel) != REPEAT_RUNNING {
+        drop(Box::from_raw(repeat));
+    }
+}
+
 #[derive(Debug)]
 pub struct CallbackParameter(*mut c_void);
 
@@ -41,3 +113,100 @@ impl<F: FnOnce() + 'static> Callback for F {
         Box::new(closure).into()
     }
 }
+
+/// Closure which can be called several times through [repeat_entry](repeat_entry)
+pub trait RepeatCallback {
+    fn into_repeat_parameter(self) -> CallbackParameter;
+}
+
+impl<F: FnMut() + Send + 'static> RepeatCallback for F {
+    fn into_repeat_parameter(self) -> CallbackParameter {
+        let repeat = Box::new(Repeat {
+            closure: Box::new(self),
+            state: AtomicU8::new(REPEAT_IDLE),
+        });
+        CallbackParameter(Box::into_raw(repeat).cast())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::sync::atomic::{AtomicPtr, AtomicUsize};
+    use std::{ptr::null_mut, sync::Arc};
+
+    /// Counts the drops of the closure capturing it
+    struct DropCount(Arc<AtomicUsize>);
+
+    impl Drop for DropCount {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn state(parameter: *mut c_void) -> u8 {
+        unsafe { (*parameter.cast::<Repeat>()).state.load(Ordering::SeqCst) }
+    }
+
+    #[test]
+    fn repeat_runs_until_released() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let drops = Arc::new(AtomicUsize::new(0));
+        let guard = DropCount(drops.clone());
+        let counter = calls.clone();
+        let parameter = (move || {
+            let _ = &guard;
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .into_repeat_parameter()
+        .get_ptr_mut();
+
+        assert_eq!(state(parameter), REPEAT_IDLE);
+        unsafe { repeat_entry(parameter) };
+        unsafe { repeat_entry(parameter) };
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(state(parameter), REPEAT_IDLE);
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+        unsafe { release_repeat_callback(parameter) };
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn repeat_released_while_running_is_dropped_by_entry() {
+        static PARAMETER: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+        let drops = Arc::new(AtomicUsize::new(0));
+        let guard = DropCount(drops.clone());
+        let parameter = (move || {
+            let _ = &guard;
+            let parameter = PARAMETER.load(Ordering::SeqCst);
+            assert_eq!(state(parameter), REPEAT_RUNNING);
+            unsafe { release_repeat_callback(parameter) };
+            assert_eq!(state(parameter), REPEAT_RELEASED);
+        })
+        .into_repeat_parameter()
+        .get_ptr_mut();
+        PARAMETER.store(parameter, Ordering::SeqCst);
+
+        unsafe { repeat_entry(parameter) };
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn repeat_entry_skips_released_closure() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let parameter = (move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .into_repeat_parameter()
+        .get_ptr_mut();
+        let repeat: *mut Repeat = parameter.cast();
+
+        unsafe { (*repeat).state.store(REPEAT_RELEASED, Ordering::SeqCst) };
+        unsafe { repeat_entry(parameter) };
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(state(parameter), REPEAT_RELEASED);
+        drop(unsafe { Box::from_raw(repeat) });
+    }
+}
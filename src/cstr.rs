@@ -1,6 +1,6 @@
 use crate::ffi::RT_NAME_MAX;
 
-use core::str::from_utf8_unchecked;
+use core::{fmt, str::from_utf8_unchecked};
 use cty::c_char;
 
 #[allow(non_camel_case_types)]
@@ -28,6 +28,18 @@ impl RtName {
     pub fn as_mut_array_str(&mut self) -> &mut [u8] {
         &mut self.buf
     }
+
+    /// The name without the trailing `\0`
+    pub fn as_str(&self) -> &str {
+        let len = self.buf.iter().position(|&c| c == 0).unwrap_or(self.buf.len());
+        core::str::from_utf8(&self.buf[..len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for RtName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
 }
 
 impl From<&str> for RtName {
@@ -0,0 +1,257 @@
+//! Condition variable built from rt-thread semaphores and mutexes
+//!
+//! rt-thread has no native condition variable. [Condvar](Condvar) keeps the number of
+//! waiting threads and wakes them through a [Semaphore](super::sem::Semaphore), while the
+//! user data is protected by a [DataMutex](super::lock::DataMutex).
+
+use super::{
+    lock::MutexGuard,
+    sem::{Semaphore, SemaphoreStatic},
+    IpcFlag, SpinLock,
+};
+use crate::{thread::ThreadContext, time::Timeout, Result, RtError};
+
+/// Whether a timed wait on a condition variable timed out
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    #[inline]
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+/// Waiting threads which have not been notified yet
+///
+/// Invariant: `waiters` plus the value of the semaphore equals the number of threads
+/// inside `wait`. Both are only changed while the scheduler is locked.
+struct State {
+    waiters: SpinLock<u32>,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            waiters: SpinLock::new(0),
+        }
+    }
+
+    fn wait<'a, T: ?Sized>(
+        &self,
+        mut sem: Semaphore,
+        guard: MutexGuard<'a, T>,
+        time: Timeout,
+    ) -> Result<(MutexGuard<'a, T>, WaitTimeoutResult)> {
+        let mutex = guard.raw();
+        {
+            let mut waiters = self.waiters.lock();
+            *waiters += 1;
+            // Must be released after being counted, otherwise a notification sent
+            // right after the release would be lost.
+            if let Err(err) = mutex.release() {
+                *waiters -= 1;
+                return Err(err);
+            }
+        }
+
+        let mut result = sem.take(time);
+        if result.is_err() {
+            let mut waiters = self.waiters.lock();
+            // A notification may have been sent between the failure and here,
+            // take it so that it is not delivered to a future waiter.
+            if sem.try_take().is_ok() {
+                result = Ok(());
+            } else {
+                *waiters -= 1;
+            }
+        }
+        let timed_out = match result {
+            Ok(_) => false,
+            Err(RtError::TimeOut) => true,
+            Err(err) => return Self::relock(guard).and(Err(err)),
+        };
+
+        Self::relock(guard).map(|guard| (guard, WaitTimeoutResult(timed_out)))
+    }
+
+    fn relock<T: ?Sized>(guard: MutexGuard<'_, T>) -> Result<MutexGuard<'_, T>> {
+        if let Err(err) = guard.raw().take(Timeout::Forever) {
+            // The mutex is not owned anymore, the guard must not release it
+            core::mem::forget(guard);
+            return Err(err);
+        }
+        Ok(guard)
+    }
+
+    fn notify_one(&self, mut sem: Semaphore) -> Result<()> {
+        let mut waiters = self.waiters.lock();
+        if *waiters > 0 {
+            *waiters -= 1;
+            sem.release()?;
+        }
+        Ok(())
+    }
+
+    fn notify_all(&self, mut sem: Semaphore) -> Result<()> {
+        let mut waiters = self.waiters.lock();
+        while *waiters > 0 {
+            *waiters -= 1;
+            sem.release()?;
+        }
+        Ok(())
+    }
+}
+
+/// A condition variable, similar to `std::sync::Condvar`
+pub struct Condvar {
+    sem: Semaphore,
+    state: State,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    pub fn create(name: &str, flag: IpcFlag) -> Result<Self> {
+        Ok(Self {
+            sem: Semaphore::create(name, 0, flag)?,
+            state: State::new(),
+        })
+    }
+
+    /// Release the mutex of `guard` and block until this condition variable is notified,
+    /// the mutex is re-acquired before returning
+    ///
+    /// Spurious wakeups are possible, use [wait_while](Condvar::wait_while) to check a
+    /// condition.
+    #[inline]
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> Result<MutexGuard<'a, T>> {
+        self.state
+            .wait(self.sem, guard, Timeout::Forever)
+            .map(|(guard, _)| guard)
+    }
+
+    /// Same as [wait](Condvar::wait) but gives up after `time`
+    #[inline]
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        time: impl Into<Timeout>,
+    ) -> Result<(MutexGuard<'a, T>, WaitTimeoutResult)> {
+        self.state.wait(self.sem, guard, time.into())
+    }
+
+    /// Block as long as `condition` returns true
+    pub fn wait_while<'a, T, F>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+    ) -> Result<MutexGuard<'a, T>>
+    where
+        T: ?Sized,
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard)?;
+        }
+        Ok(guard)
+    }
+
+    /// Wake up one blocked thread
+    #[inline]
+    pub fn notify_one(&self) -> Result<()> {
+        self.state.notify_one(self.sem)
+    }
+
+    /// Wake up all blocked threads
+    #[inline]
+    pub fn notify_all(&self) -> Result<()> {
+        self.state.notify_all(self.sem)
+    }
+}
+
+impl Drop for Condvar {
+    fn drop(&mut self) {
+        self.sem.delete().ok();
+    }
+}
+
+/// Condition variable which can be placed in a `static` item
+///
+/// [init](CondvarStatic::init) must be called before the first wait. Waiting and
+/// notifying take a [ThreadContext](ThreadContext), like locking a
+/// [StaticDataMutex](super::lock::StaticDataMutex).
+pub struct CondvarStatic {
+    sem: SemaphoreStatic,
+    state: State,
+}
+
+unsafe impl Send for CondvarStatic {}
+unsafe impl Sync for CondvarStatic {}
+
+impl CondvarStatic {
+    pub const fn new() -> Self {
+        Self {
+            sem: SemaphoreStatic::new(),
+            state: State::new(),
+        }
+    }
+
+    #[inline]
+    pub fn init(&'static self, name: &str, flag: IpcFlag) -> Result<()> {
+        self.sem.init(name, 0, flag)
+    }
+
+    #[inline]
+    pub fn detach(&'static self) -> Result<()> {
+        self.sem.detach()
+    }
+
+    #[inline]
+    pub fn wait<'a, T: ?Sized>(
+        &'static self,
+        guard: MutexGuard<'a, T>,
+        ctx: ThreadContext,
+    ) -> Result<MutexGuard<'a, T>> {
+        self.state
+            .wait(self.sem.get(ctx), guard, Timeout::Forever)
+            .map(|(guard, _)| guard)
+    }
+
+    #[inline]
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &'static self,
+        guard: MutexGuard<'a, T>,
+        time: impl Into<Timeout>,
+        ctx: ThreadContext,
+    ) -> Result<(MutexGuard<'a, T>, WaitTimeoutResult)> {
+        self.state.wait(self.sem.get(ctx), guard, time.into())
+    }
+
+    pub fn wait_while<'a, T, F>(
+        &'static self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: F,
+        ctx: ThreadContext,
+    ) -> Result<MutexGuard<'a, T>>
+    where
+        T: ?Sized,
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard, ctx)?;
+        }
+        Ok(guard)
+    }
+
+    #[inline]
+    pub fn notify_one(&'static self, ctx: ThreadContext) -> Result<()> {
+        self.state.notify_one(self.sem.get(ctx))
+    }
+
+    #[inline]
+    pub fn notify_all(&'static self, ctx: ThreadContext) -> Result<()> {
+        self.state.notify_all(self.sem.get(ctx))
+    }
+}
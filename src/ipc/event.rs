@@ -0,0 +1,343 @@
+//! Event set is a set of 32 bits used for thread synchronization.
+//!
+//! One thread can wait for several events at once, either until all of them arrive
+//! ("and trigger") or until any of them arrives ("or trigger"). Events can be sent
+//! by threads and by interrupt handlers.
+
+use super::IpcFlag;
+use crate::{
+    cstr::RtName,
+    ffi::{
+        rt_event, rt_event_create, rt_event_delete, rt_event_detach, rt_event_init,
+        rt_event_recv, rt_event_send, rt_event_t, rt_object, RT_EVENT_FLAG_AND,
+        RT_EVENT_FLAG_CLEAR, RT_EVENT_FLAG_OR,
+    },
+    isr::{IsrContext, IsrEvent},
+    object::Object,
+    thread::ThreadContext,
+    time::Timeout,
+    Result, RtError,
+};
+use core::{
+    cell::UnsafeCell,
+    marker::{PhantomData, PhantomPinned},
+    mem::MaybeUninit,
+    ptr::NonNull,
+};
+
+/// Which events of the set [Event::recv](Event::recv) waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Wait until all the events in the set arrive
+    All,
+    /// Wait until any of the events in the set arrives
+    Any,
+}
+
+/// Receiving options of [Event::recv](Event::recv)
+///
+/// rt-thread requires exactly one trigger, so the options cannot express anything
+/// else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventOption {
+    pub trigger: Trigger,
+    /// Clear the received events after receiving
+    pub clear: bool,
+}
+
+impl EventOption {
+    #[inline]
+    pub const fn new(trigger: Trigger, clear: bool) -> Self {
+        EventOption { trigger, clear }
+    }
+
+    fn bits(&self) -> u8 {
+        let trigger = match self.trigger {
+            Trigger::All => RT_EVENT_FLAG_AND,
+            Trigger::Any => RT_EVENT_FLAG_OR,
+        };
+        let clear = if self.clear { RT_EVENT_FLAG_CLEAR } else { 0 };
+        (trigger | clear) as u8
+    }
+}
+
+/// A set of events that can be carried by an rt-thread event set
+///
+/// Use [event_set!](crate::event_set) to implement it for a `bitflags` type.
+pub trait EventSet: Copy {
+    fn bits(&self) -> u32;
+    fn from_bits_truncate(bits: u32) -> Self;
+}
+
+impl EventSet for u32 {
+    #[inline]
+    fn bits(&self) -> u32 {
+        *self
+    }
+
+    #[inline]
+    fn from_bits_truncate(bits: u32) -> Self {
+        bits
+    }
+}
+
+/// Implement [EventSet](crate::ipc::event::EventSet) for a type generated by `bitflags!`
+///
+/// The flags must fit in 32 bits, wider types fail to compile.
+///
+/// ```ignore
+/// bitflags! {
+///     struct Signals: u32 {
+///         const RX_DONE = 0b01;
+///         const TX_DONE = 0b10;
+///     }
+/// }
+/// event_set!(Signals);
+/// ```
+#[macro_export]
+macro_rules! event_set {
+    ($t:ty) => {
+        // Event sets are 32 bits wide, wider flags would be truncated
+        const _: [(); 0 - !(::core::mem::size_of::<$t>() <= 4) as usize] = [];
+
+        impl $crate::ipc::event::EventSet for $t {
+            #[inline]
+            fn bits(&self) -> u32 {
+                <$t>::bits(self) as u32
+            }
+
+            #[inline]
+            fn from_bits_truncate(bits: u32) -> Self {
+                <$t>::from_bits_truncate(bits as _)
+            }
+        }
+    };
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Event {
+    raw: rt_event_t,
+}
+
+unsafe impl Send for Event {}
+unsafe impl Sync for Event {}
+
+impl Event {
+    ///
+    /// This function will create an event object from system resource
+    ///
+    /// @param name the name of event
+    /// @param flag the flag of event
+    ///
+    /// @return the created event
+    ///
+    pub fn create(name: &str, flag: IpcFlag) -> Result<Self> {
+        let name: RtName = name.into();
+        let result = unsafe { rt_event_create(name.into(), flag.into()) };
+        if result.is_null() {
+            Err(RtError::Error)
+        } else {
+            Ok(Event { raw: result })
+        }
+    }
+
+    ///
+    /// This function will delete an event object and release the memory
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn delete(self) -> Result<()> {
+        let err = unsafe { rt_event_delete(self.raw) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will send an event to the event object, if there are threads
+    /// suspended on event object, it will be waked up.
+    ///
+    /// @param set the event set
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn send(&self, set: u32) -> Result<()> {
+        let err = unsafe { rt_event_send(self.raw, set) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will receive an event from event object, if the event is
+    /// unavailable, the thread shall wait for a specified time.
+    ///
+    /// @param set the interested event set
+    /// @param option the receive option
+    /// @param time the waiting time
+    ///
+    /// @return the received events
+    ///
+    pub fn recv(&self, set: u32, option: EventOption, time: impl Into<Timeout>) -> Result<u32> {
+        let mut recved = 0;
+        let err = unsafe {
+            rt_event_recv(
+                self.raw,
+                set,
+                option.bits(),
+                time.into().to_raw(),
+                &mut recved,
+            )
+        };
+        RtError::from_code_none(err, recved)
+    }
+
+    /// Wait until all the events in `set` arrive and clear them
+    #[inline]
+    pub fn recv_all(&self, set: u32, time: impl Into<Timeout>) -> Result<u32> {
+        self.recv(set, EventOption::new(Trigger::All, true), time)
+    }
+
+    /// Wait until any of the events in `set` arrives and clear the received ones
+    #[inline]
+    pub fn recv_any(&self, set: u32, time: impl Into<Timeout>) -> Result<u32> {
+        self.recv(set, EventOption::new(Trigger::Any, true), time)
+    }
+
+    /// View this event object as a set of typed flags
+    #[inline]
+    pub fn typed<F: EventSet>(self) -> TypedEvent<F> {
+        TypedEvent {
+            event: self,
+            _flags: PhantomData,
+        }
+    }
+}
+
+impl Object for Event {
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        NonNull::new(self.raw.cast()).expect("Unexpected null rt_event_t")
+    }
+}
+
+pub struct EventStatic {
+    raw: UnsafeCell<MaybeUninit<rt_event>>,
+    _pinned: PhantomPinned,
+}
+
+unsafe impl Send for EventStatic {}
+unsafe impl Sync for EventStatic {}
+
+impl EventStatic {
+    pub const fn new() -> Self {
+        EventStatic {
+            raw: UnsafeCell::new(core::mem::MaybeUninit::uninit()),
+            _pinned: PhantomPinned {},
+        }
+    }
+    pub fn init(&'static self, name: &str, flag: IpcFlag) -> Result<()> {
+        let name: RtName = name.into();
+        let err = unsafe { rt_event_init(self.raw.get().cast(), name.into(), flag.into()) };
+        RtError::from_code_none(err, ())
+    }
+
+    #[inline]
+    pub fn detach(&'static self) -> Result<()> {
+        let err = unsafe { rt_event_detach(self.raw.get().cast()) };
+        RtError::from_code_none(err, ())
+    }
+
+    pub fn get(&'static self, _ctx: ThreadContext) -> Event {
+        Event {
+            raw: self.raw.get().cast(),
+        }
+    }
+
+    #[inline]
+    pub fn get_typed<F: EventSet>(&'static self, ctx: ThreadContext) -> TypedEvent<F> {
+        self.get(ctx).typed()
+    }
+
+    /// Send-only view for interrupt handlers
+    #[inline]
+    pub fn get_isr(&'static self, _isr: IsrContext<'_>) -> IsrEvent {
+        IsrEvent::from(Event {
+            raw: self.raw.get().cast(),
+        })
+    }
+}
+
+/// Event object carrying a user defined [EventSet](EventSet) instead of raw `u32` masks
+pub struct TypedEvent<F> {
+    event: Event,
+    _flags: PhantomData<F>,
+}
+
+impl<F> Clone for TypedEvent<F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F> Copy for TypedEvent<F> {}
+
+unsafe impl<F> Send for TypedEvent<F> {}
+unsafe impl<F> Sync for TypedEvent<F> {}
+
+impl<F: EventSet> TypedEvent<F> {
+    #[inline]
+    pub fn create(name: &str, flag: IpcFlag) -> Result<Self> {
+        Event::create(name, flag).map(Event::typed)
+    }
+
+    #[inline]
+    pub fn delete(self) -> Result<()> {
+        self.event.delete()
+    }
+
+    #[inline]
+    pub fn send(&self, set: F) -> Result<()> {
+        self.event.send(set.bits())
+    }
+
+    #[inline]
+    pub fn recv(&self, set: F, option: EventOption, time: impl Into<Timeout>) -> Result<F> {
+        self.event
+            .recv(set.bits(), option, time)
+            .map(F::from_bits_truncate)
+    }
+
+    #[inline]
+    pub fn recv_all(&self, set: F, time: impl Into<Timeout>) -> Result<F> {
+        self.recv(set, EventOption::new(Trigger::All, true), time)
+    }
+
+    #[inline]
+    pub fn recv_any(&self, set: F, time: impl Into<Timeout>) -> Result<F> {
+        self.recv(set, EventOption::new(Trigger::Any, true), time)
+    }
+
+    /// Get the untyped event object
+    #[inline]
+    pub fn raw(&self) -> Event {
+        self.event
+    }
+}
+
+impl<F> Object for TypedEvent<F> {
+    #[inline]
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        self.event.get_ptr()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn option_has_exactly_one_trigger() {
+        let all = EventOption::new(Trigger::All, false).bits() as u32;
+        assert_eq!(all, RT_EVENT_FLAG_AND);
+        let any = EventOption::new(Trigger::Any, true).bits() as u32;
+        assert_eq!(any, RT_EVENT_FLAG_OR | RT_EVENT_FLAG_CLEAR);
+    }
+}
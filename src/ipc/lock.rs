@@ -0,0 +1,210 @@
+//! Data-owning mutex built on top of [rt_mutex](super::mutex)
+//!
+//! Unlike the bare [Mutex handle](super::mutex::Mutex), the protected data can only be
+//! reached through a [MutexGuard](MutexGuard), which releases the rt-thread mutex when
+//! it goes out of scope.
+//!
+//! rt-thread mutexes are recursive, but taking the lock again from the owner thread
+//! would hand out a second mutable reference. Locking a mutex which is already held by
+//! the current thread fails with `RtError::Busy`.
+
+use super::{
+    mutex::{Mutex as RawMutex, MutexStatic},
+    IpcFlag,
+};
+use crate::{thread::ThreadContext, time::Timeout, Result, RtError};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+
+/// A mutual exclusion primitive protecting `T`, similar to `std::sync::Mutex`
+///
+/// Named apart from the bare [Mutex handle](super::mutex::Mutex) it wraps.
+pub struct DataMutex<T: ?Sized> {
+    raw: RawMutex,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for DataMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for DataMutex<T> {}
+
+/// Mutex protecting `T` which can be placed in a `static` item
+///
+/// [init](StaticDataMutex::init) must be called before the first lock. Locking takes a
+/// [ThreadContext](ThreadContext), so that code running in interrupt context cannot
+/// reach the mutex through the `static`.
+pub struct StaticDataMutex<T: ?Sized> {
+    raw: MutexStatic,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for StaticDataMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for StaticDataMutex<T> {}
+
+/// RAII guard of [DataMutex](DataMutex) and [StaticDataMutex](StaticDataMutex), the
+/// lock is released when the guard is dropped
+#[must_use = "if unused the DataMutex will immediately unlock"]
+pub struct MutexGuard<'a, T: ?Sized + 'a> {
+    raw: RawMutex,
+    data: &'a UnsafeCell<T>,
+}
+
+// Sharing the guard only shares `&T`
+unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
+
+#[inline]
+fn take<'a, T: ?Sized>(
+    raw: RawMutex,
+    data: &'a UnsafeCell<T>,
+    time: Timeout,
+) -> Result<MutexGuard<'a, T>> {
+    if raw.is_owned_by_current() {
+        return Err(RtError::Busy);
+    }
+    raw.take(time)?;
+    Ok(MutexGuard { raw, data })
+}
+
+impl<T> DataMutex<T> {
+    pub fn create(name: &str, flag: IpcFlag, data: T) -> Result<Self> {
+        Ok(Self {
+            raw: RawMutex::create(name, flag)?,
+            data: UnsafeCell::new(data),
+        })
+    }
+
+    /// Delete the rt-thread mutex and return the protected data
+    pub fn into_inner(self) -> T {
+        let this = ManuallyDrop::new(self);
+        this.raw.delete().ok();
+        unsafe { core::ptr::read(&this.data) }.into_inner()
+    }
+}
+
+impl<T: ?Sized> DataMutex<T> {
+    /// Acquire the mutex, blocking the current thread until it is able to do so
+    #[inline]
+    pub fn lock(&self) -> Result<MutexGuard<'_, T>> {
+        take(self.raw, &self.data, Timeout::Forever)
+    }
+
+    /// Attempt to acquire the mutex without blocking
+    #[inline]
+    pub fn try_lock(&self) -> Result<MutexGuard<'_, T>> {
+        take(self.raw, &self.data, Timeout::NoWait)
+    }
+
+    /// Acquire the mutex, waiting at most `time`
+    #[inline]
+    pub fn lock_timeout(&self, time: impl Into<Timeout>) -> Result<MutexGuard<'_, T>> {
+        take(self.raw, &self.data, time.into())
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        // We know statically that there are no other references to `self`, so
+        // there's no need to lock the inner mutex.
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Get the underlying rt-thread mutex
+    #[inline]
+    pub fn raw(&self) -> RawMutex {
+        self.raw
+    }
+}
+
+impl<T: ?Sized> Drop for DataMutex<T> {
+    fn drop(&mut self) {
+        self.raw.delete().ok();
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for DataMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataMutex").field("raw", &self.raw).finish()
+    }
+}
+
+impl<T> StaticDataMutex<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            raw: MutexStatic::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+}
+
+impl<T: ?Sized> StaticDataMutex<T> {
+    #[inline]
+    pub fn init(&'static self, name: &str, flag: IpcFlag) -> Result<()> {
+        self.raw.init(name, flag)
+    }
+
+    #[inline]
+    pub fn detach(&'static self) -> Result<()> {
+        self.raw.detach()
+    }
+
+    #[inline]
+    pub fn lock(&'static self, ctx: ThreadContext) -> Result<MutexGuard<'static, T>> {
+        take(self.raw.get(ctx), &self.data, Timeout::Forever)
+    }
+
+    #[inline]
+    pub fn try_lock(&'static self, ctx: ThreadContext) -> Result<MutexGuard<'static, T>> {
+        take(self.raw.get(ctx), &self.data, Timeout::NoWait)
+    }
+
+    #[inline]
+    pub fn lock_timeout(
+        &'static self,
+        time: impl Into<Timeout>,
+        ctx: ThreadContext,
+    ) -> Result<MutexGuard<'static, T>> {
+        take(self.raw.get(ctx), &self.data, time.into())
+    }
+
+    /// Get the underlying rt-thread mutex
+    #[inline]
+    pub fn raw(&'static self, ctx: ThreadContext) -> RawMutex {
+        self.raw.get(ctx)
+    }
+}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    /// Get the underlying rt-thread mutex of the guard
+    #[inline]
+    pub fn raw(&self) -> RawMutex {
+        self.raw
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.raw.release().ok();
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
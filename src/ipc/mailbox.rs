@@ -0,0 +1,444 @@
+//! Mailbox is a typical inter-thread communication method which transfers
+//! `usize` sized mails between threads or from interrupt handlers.
+//!
+//! [TypedMailbox](TypedMailbox) carries `Box<T>` pointers as mails, so the ownership
+//! of the boxed value moves from the sending thread to the receiving thread. It owns
+//! the mailbox and deletes it when dropped, while [TypedMailboxRef](TypedMailboxRef)
+//! only borrows it.
+
+use super::IpcFlag;
+use crate::{
+    cstr::RtName,
+    ffi::{
+        rt_mailbox, rt_mailbox_t, rt_mb_create, rt_mb_delete, rt_mb_detach, rt_mb_init,
+        rt_mb_recv, rt_mb_send, rt_mb_send_wait, rt_object, rt_size_t, rt_ubase_t,
+    },
+    isr::{IsrContext, IsrMailbox},
+    object::Object,
+    thread::ThreadContext,
+    time::Timeout,
+    Result, RtError,
+};
+#[cfg(feature = "alloc")]
+use crate::Box;
+use core::{
+    cell::UnsafeCell,
+    marker::{PhantomData, PhantomPinned},
+    mem::MaybeUninit,
+    ptr::NonNull,
+};
+
+/// Error returned when a boxed value cannot be sent, giving the value back
+#[derive(Debug)]
+pub struct SendError<T> {
+    pub error: RtError,
+    pub value: T,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Mailbox {
+    raw: rt_mailbox_t,
+}
+
+unsafe impl Send for Mailbox {}
+unsafe impl Sync for Mailbox {}
+
+impl Mailbox {
+    ///
+    /// This function will create a mailbox object from system resource
+    ///
+    /// @param name the name of mailbox
+    /// @param size the size of mailbox
+    /// @param flag the flag of mailbox
+    ///
+    /// @return the created mailbox
+    ///
+    pub fn create(name: &str, size: usize, flag: IpcFlag) -> Result<Self> {
+        let name: RtName = name.into();
+        let result = unsafe { rt_mb_create(name.into(), size as rt_size_t, flag.into()) };
+        if result.is_null() {
+            Err(RtError::Error)
+        } else {
+            Ok(Mailbox { raw: result })
+        }
+    }
+
+    ///
+    /// This function will delete a mailbox object and release the memory
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn delete(self) -> Result<()> {
+        let err = unsafe { rt_mb_delete(self.raw) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will send a mail to mailbox object, if there are threads
+    /// suspended on mailbox object, it will be waked up. This function will return
+    /// immediately, if you want blocking send, use rt_mb_send_wait instead.
+    ///
+    /// @param value the mail
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn send(&self, value: usize) -> Result<()> {
+        let err = unsafe { rt_mb_send(self.raw, value as rt_ubase_t) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will send a mail to mailbox object. If the mailbox is full,
+    /// current thread will be suspended until timeout.
+    ///
+    /// @param value the mail
+    /// @param time the waiting time
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn send_wait(&self, value: usize, time: impl Into<Timeout>) -> Result<()> {
+        let time = time.into().to_raw();
+        let err = unsafe { rt_mb_send_wait(self.raw, value as rt_ubase_t, time) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will receive a mail from mailbox object, if there is no mail
+    /// in mailbox object, the thread shall wait for a specified time.
+    ///
+    /// @param time the waiting time
+    ///
+    /// @return the received mail
+    ///
+    pub fn recv(&self, time: impl Into<Timeout>) -> Result<usize> {
+        let mut value: rt_ubase_t = 0;
+        let err = unsafe { rt_mb_recv(self.raw, &mut value, time.into().to_raw()) };
+        RtError::from_code_none(err, value as usize)
+    }
+
+    /// Number of mails in the mailbox
+    #[inline]
+    pub fn len(&self) -> usize {
+        unsafe { (*self.raw).entry as usize }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Capacity of the mailbox
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        unsafe { (*self.raw).size as usize }
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Take the ownership of this mailbox as a carrier of `Box<T>`
+    ///
+    /// ## Safety
+    ///
+    /// The mailbox must have been created by [create](Mailbox::create) and must not
+    /// be deleted elsewhere. All the mails in the mailbox must be sent through the
+    /// returned [TypedMailbox](TypedMailbox) or a mailbox of the same type.
+    #[inline]
+    pub unsafe fn typed<T: Send>(self) -> TypedMailbox<T> {
+        TypedMailbox {
+            mailbox: self,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl Object for Mailbox {
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        NonNull::new(self.raw.cast()).expect("Unexpected null rt_mailbox_t")
+    }
+}
+
+/// Statically allocated mailbox which holds at most `N` mails
+pub struct MailboxStatic<const N: usize> {
+    raw: UnsafeCell<MaybeUninit<rt_mailbox>>,
+    pool: UnsafeCell<MaybeUninit<[rt_ubase_t; N]>>,
+    _pinned: PhantomPinned,
+}
+
+unsafe impl<const N: usize> Send for MailboxStatic<N> {}
+unsafe impl<const N: usize> Sync for MailboxStatic<N> {}
+
+impl<const N: usize> MailboxStatic<N> {
+    pub const fn new() -> Self {
+        MailboxStatic {
+            raw: UnsafeCell::new(MaybeUninit::uninit()),
+            pool: UnsafeCell::new(MaybeUninit::uninit()),
+            _pinned: PhantomPinned {},
+        }
+    }
+
+    pub fn init(&'static self, name: &str, flag: IpcFlag) -> Result<()> {
+        let name: RtName = name.into();
+        let err = unsafe {
+            rt_mb_init(
+                self.raw.get().cast(),
+                name.into(),
+                self.pool.get().cast(),
+                N as rt_size_t,
+                flag.into(),
+            )
+        };
+        RtError::from_code_none(err, ())
+    }
+
+    #[inline]
+    pub fn detach(&'static self) -> Result<()> {
+        let err = unsafe { rt_mb_detach(self.raw.get().cast()) };
+        RtError::from_code_none(err, ())
+    }
+
+    pub fn get(&'static self, _ctx: ThreadContext) -> Mailbox {
+        Mailbox {
+            raw: self.raw.get().cast(),
+        }
+    }
+
+    /// Non-blocking send-only view for interrupt handlers
+    #[inline]
+    pub fn get_isr(&'static self, _isr: IsrContext<'_>) -> IsrMailbox {
+        IsrMailbox::from(Mailbox {
+            raw: self.raw.get().cast(),
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Mailbox which transfers the ownership of `Box<T>` between threads
+///
+/// The mailbox is deleted when dropped, dropping the values still queued. Use
+/// [by_ref](TypedMailbox::by_ref) to share it with scoped threads.
+pub struct TypedMailbox<T> {
+    mailbox: Mailbox,
+    _value: PhantomData<Box<T>>,
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send> Send for TypedMailbox<T> {}
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send> Sync for TypedMailbox<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T: Send> TypedMailbox<T> {
+    #[inline]
+    pub fn create(name: &str, size: usize, flag: IpcFlag) -> Result<Self> {
+        Mailbox::create(name, size, flag).map(|mailbox| unsafe { mailbox.typed() })
+    }
+
+    /// Drop all the queued values and delete the mailbox, reporting the error
+    /// [Drop](Drop) would ignore
+    pub fn delete(self) -> Result<()> {
+        let mailbox = core::mem::ManuallyDrop::new(self);
+        mailbox.clear();
+        mailbox.mailbox.delete()
+    }
+
+    /// Borrow the mailbox
+    #[inline]
+    pub fn by_ref(&self) -> TypedMailboxRef<'_, T> {
+        TypedMailboxRef {
+            mailbox: self.mailbox,
+            _value: PhantomData,
+        }
+    }
+
+    /// Send a boxed value without waiting, the value is given back on failure
+    #[inline]
+    pub fn send(&self, value: Box<T>) -> core::result::Result<(), SendError<Box<T>>> {
+        self.by_ref().send(value)
+    }
+
+    /// Send a boxed value, waiting for a free slot if the mailbox is full
+    #[inline]
+    pub fn send_wait(
+        &self,
+        value: Box<T>,
+        time: impl Into<Timeout>,
+    ) -> core::result::Result<(), SendError<Box<T>>> {
+        self.by_ref().send_wait(value, time)
+    }
+
+    #[inline]
+    pub fn recv(&self, time: impl Into<Timeout>) -> Result<Box<T>> {
+        self.by_ref().recv(time)
+    }
+
+    /// Drop all the values currently queued
+    #[inline]
+    pub fn clear(&self) {
+        self.by_ref().clear()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.mailbox.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.mailbox.is_empty()
+    }
+
+    /// Get the untyped mailbox
+    ///
+    /// The mailbox must not be deleted through it, nor used after this
+    /// [TypedMailbox](TypedMailbox) is dropped.
+    #[inline]
+    pub fn raw(&self) -> Mailbox {
+        self.mailbox
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Drop for TypedMailbox<T> {
+    fn drop(&mut self) {
+        while let Ok(ptr) = self.mailbox.recv(Timeout::NoWait) {
+            drop(unsafe { Box::from_raw(ptr as *mut T) });
+        }
+        self.mailbox.delete().ok();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Object for TypedMailbox<T> {
+    #[inline]
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        self.mailbox.get_ptr()
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// A borrowed [TypedMailbox](TypedMailbox) which cannot delete the mailbox
+pub struct TypedMailboxRef<'a, T> {
+    mailbox: Mailbox,
+    _value: PhantomData<&'a TypedMailbox<T>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Clone for TypedMailboxRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Copy for TypedMailboxRef<'_, T> {}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send> Send for TypedMailboxRef<'_, T> {}
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send> Sync for TypedMailboxRef<'_, T> {}
+
+#[cfg(feature = "alloc")]
+impl<T: Send> TypedMailboxRef<'_, T> {
+    /// Send a boxed value without waiting, the value is given back on failure
+    pub fn send(&self, value: Box<T>) -> core::result::Result<(), SendError<Box<T>>> {
+        let ptr = Box::into_raw(value);
+        self.mailbox
+            .send(ptr as usize)
+            .map_err(|error| SendError {
+                error,
+                value: unsafe { Box::from_raw(ptr) },
+            })
+    }
+
+    /// Send a boxed value, waiting for a free slot if the mailbox is full
+    pub fn send_wait(
+        &self,
+        value: Box<T>,
+        time: impl Into<Timeout>,
+    ) -> core::result::Result<(), SendError<Box<T>>> {
+        let ptr = Box::into_raw(value);
+        self.mailbox
+            .send_wait(ptr as usize, time)
+            .map_err(|error| SendError {
+                error,
+                value: unsafe { Box::from_raw(ptr) },
+            })
+    }
+
+    #[inline]
+    pub fn recv(&self, time: impl Into<Timeout>) -> Result<Box<T>> {
+        self.mailbox
+            .recv(time)
+            .map(|ptr| unsafe { Box::from_raw(ptr as *mut T) })
+    }
+
+    /// Drop all the values currently queued
+    pub fn clear(&self) {
+        while let Ok(value) = self.recv(Timeout::NoWait) {
+            drop(value);
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.mailbox.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.mailbox.is_empty()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Object for TypedMailboxRef<'_, T> {
+    #[inline]
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        self.mailbox.get_ptr()
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Statically allocated [TypedMailbox](TypedMailbox) which holds at most `N` values
+pub struct TypedMailboxStatic<T, const N: usize> {
+    mailbox: MailboxStatic<N>,
+    _value: PhantomData<Box<T>>,
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send, const N: usize> Send for TypedMailboxStatic<T, N> {}
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send, const N: usize> Sync for TypedMailboxStatic<T, N> {}
+
+#[cfg(feature = "alloc")]
+impl<T: Send, const N: usize> TypedMailboxStatic<T, N> {
+    pub const fn new() -> Self {
+        TypedMailboxStatic {
+            mailbox: MailboxStatic::new(),
+            _value: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn init(&'static self, name: &str, flag: IpcFlag) -> Result<()> {
+        self.mailbox.init(name, flag)
+    }
+
+    /// Drop all the queued values and detach the mailbox
+    ///
+    /// No thread shall send to the mailbox concurrently.
+    pub fn detach(&'static self, ctx: ThreadContext) -> Result<()> {
+        self.get(ctx).clear();
+        self.mailbox.detach()
+    }
+
+    /// Borrow the static mailbox, which must have been initialized
+    pub fn get(&'static self, ctx: ThreadContext) -> TypedMailboxRef<'static, T> {
+        TypedMailboxRef {
+            mailbox: self.mailbox.get(ctx),
+            _value: PhantomData,
+        }
+    }
+}
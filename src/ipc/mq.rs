@@ -0,0 +1,255 @@
+//! Message queue is an extension of mailbox which transfers messages of fixed size.
+//!
+//! The messages are copied into the memory pool of the queue, so each element of a
+//! [MessageQueue](MessageQueue) is a plain `T: Copy` value.
+
+use super::IpcFlag;
+use crate::{
+    cstr::RtName,
+    ffi::{
+        rt_messagequeue, rt_mq_create, rt_mq_delete, rt_mq_detach, rt_mq_init, rt_mq_recv,
+        rt_mq_send, rt_mq_t, rt_mq_urgent, rt_object, rt_size_t,
+    },
+    isr::{IsrContext, IsrMessageQueue},
+    object::Object,
+    thread::ThreadContext,
+    time::Timeout,
+    Result, RtError,
+};
+use core::{
+    cell::UnsafeCell,
+    ffi::c_void,
+    marker::{PhantomData, PhantomPinned},
+    mem::{size_of, MaybeUninit},
+    ptr::NonNull,
+};
+
+pub struct MessageQueue<T> {
+    raw: rt_mq_t,
+    _msg: PhantomData<T>,
+}
+
+impl<T> Clone for MessageQueue<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for MessageQueue<T> {}
+
+unsafe impl<T: Send> Send for MessageQueue<T> {}
+unsafe impl<T: Send> Sync for MessageQueue<T> {}
+
+impl<T: Copy + Send> MessageQueue<T> {
+    ///
+    /// This function will create a message queue object from system resource
+    ///
+    /// @param name the name of message queue
+    /// @param max_msgs the maximum number of message in queue
+    /// @param flag the flag of message queue
+    ///
+    /// @return the created message queue
+    ///
+    pub fn create(name: &str, max_msgs: usize, flag: IpcFlag) -> Result<Self> {
+        let name: RtName = name.into();
+        let result = unsafe {
+            rt_mq_create(
+                name.into(),
+                size_of::<T>() as rt_size_t,
+                max_msgs as rt_size_t,
+                flag.into(),
+            )
+        };
+        if result.is_null() {
+            Err(RtError::Error)
+        } else {
+            Ok(MessageQueue {
+                raw: result,
+                _msg: PhantomData,
+            })
+        }
+    }
+
+    ///
+    /// This function will delete a message queue object and release the memory
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn delete(self) -> Result<()> {
+        let err = unsafe { rt_mq_delete(self.raw) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will send a message to message queue object, if there are
+    /// threads suspended on message queue object, it will be waked up.
+    ///
+    /// @param msg the message
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn send(&self, mut msg: T) -> Result<()> {
+        let buffer: *mut T = &mut msg;
+        let err = unsafe { rt_mq_send(self.raw, buffer.cast(), size_of::<T>() as rt_size_t) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will send an urgent message to message queue object, which
+    /// means the message will be placed to the head of message queue. If there
+    /// are threads suspended on message queue object, it will be waked up.
+    ///
+    /// @param msg the message
+    ///
+    /// @return the error code
+    ///
+    #[inline]
+    pub fn send_urgent(&self, mut msg: T) -> Result<()> {
+        let buffer: *mut T = &mut msg;
+        let err = unsafe { rt_mq_urgent(self.raw, buffer.cast(), size_of::<T>() as rt_size_t) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will receive a message from message queue object, if there is
+    /// no message in message queue object, the thread shall wait for a specified
+    /// time.
+    ///
+    /// @param time the waiting time
+    ///
+    /// @return the received message
+    ///
+    pub fn recv(&self, time: impl Into<Timeout>) -> Result<T> {
+        let mut msg = MaybeUninit::<T>::uninit();
+        let err = unsafe {
+            rt_mq_recv(
+                self.raw,
+                msg.as_mut_ptr().cast(),
+                size_of::<T>() as rt_size_t,
+                time.into().to_raw(),
+            )
+        };
+        RtError::from_code_none_then(err, || unsafe { msg.assume_init() })
+    }
+
+    /// Number of messages in the queue
+    #[inline]
+    pub fn len(&self) -> usize {
+        unsafe { (*self.raw).entry as usize }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of messages in the queue
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        unsafe { (*self.raw).max_msgs as usize }
+    }
+}
+
+impl<T> Object for MessageQueue<T> {
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        NonNull::new(self.raw.cast()).expect("Unexpected null rt_mq_t")
+    }
+}
+
+/// Layout of a message in the memory pool: a list header followed by the message
+#[repr(C)]
+struct MessageSlot<T> {
+    next: *mut c_void,
+    msg: MaybeUninit<T>,
+}
+
+/// Statically allocated message queue with a pool of `N` messages
+pub struct MessageQueueStatic<T, const N: usize> {
+    raw: UnsafeCell<MaybeUninit<rt_messagequeue>>,
+    pool: UnsafeCell<MaybeUninit<[MessageSlot<T>; N]>>,
+    _pinned: PhantomPinned,
+}
+
+unsafe impl<T: Send, const N: usize> Send for MessageQueueStatic<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for MessageQueueStatic<T, N> {}
+
+impl<T: Copy + Send, const N: usize> MessageQueueStatic<T, N> {
+    pub const fn new() -> Self {
+        MessageQueueStatic {
+            raw: UnsafeCell::new(MaybeUninit::uninit()),
+            pool: UnsafeCell::new(MaybeUninit::uninit()),
+            _pinned: PhantomPinned {},
+        }
+    }
+
+    pub fn init(&'static self, name: &str, flag: IpcFlag) -> Result<()> {
+        let name: RtName = name.into();
+        let err = unsafe {
+            rt_mq_init(
+                self.raw.get().cast(),
+                name.into(),
+                self.pool.get().cast(),
+                size_of::<T>() as rt_size_t,
+                size_of::<[MessageSlot<T>; N]>() as rt_size_t,
+                flag.into(),
+            )
+        };
+        RtError::from_code_none(err, ())
+    }
+
+    #[inline]
+    pub fn detach(&'static self) -> Result<()> {
+        let err = unsafe { rt_mq_detach(self.raw.get().cast()) };
+        RtError::from_code_none(err, ())
+    }
+
+    pub fn get(&'static self, _ctx: ThreadContext) -> MessageQueue<T> {
+        MessageQueue {
+            raw: self.raw.get().cast(),
+            _msg: PhantomData,
+        }
+    }
+
+    /// Non-blocking send-only view for interrupt handlers
+    #[inline]
+    pub fn get_isr(&'static self, _isr: IsrContext<'_>) -> IsrMessageQueue<T> {
+        IsrMessageQueue::from(MessageQueue {
+            raw: self.raw.get().cast(),
+            _msg: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::RT_ALIGN_SIZE;
+
+    /// Messages rt-thread carves out of the pool of a
+    /// [MessageQueueStatic](MessageQueueStatic), each one being the aligned message
+    /// size plus a list header
+    fn capacity<T, const N: usize>() -> usize {
+        let align = RT_ALIGN_SIZE as usize;
+        let msg_size = (size_of::<T>() + align - 1) / align * align;
+        size_of::<[MessageSlot<T>; N]>() / (msg_size + size_of::<*mut c_void>())
+    }
+
+    fn check<T, const N: usize>() {
+        assert!(capacity::<T, N>() >= N);
+    }
+
+    #[test]
+    fn static_queue_holds_n_messages() {
+        check::<u8, 1>();
+        check::<u8, 7>();
+        check::<u16, 3>();
+        check::<u32, 5>();
+        check::<u64, 4>();
+        check::<[u8; 3], 4>();
+        check::<[u8; 5], 9>();
+        check::<(u32, u8), 6>();
+        check::<[u32; 16], 2>();
+    }
+}
@@ -3,9 +3,11 @@ use crate::{
     cstr::RtName,
     ffi::{
         rt_mutex, rt_mutex_create, rt_mutex_delete, rt_mutex_detach, rt_mutex_init,
-        rt_mutex_release, rt_mutex_t, rt_mutex_take, rt_object,
+        rt_mutex_release, rt_mutex_t, rt_mutex_take, rt_object, rt_thread_self,
     },
     object::Object,
+    thread::ThreadContext,
+    time::Timeout,
     Result, RtError,
 };
 use core::{cell::UnsafeCell, marker::PhantomPinned, mem::MaybeUninit, ptr::NonNull};
@@ -36,8 +38,8 @@ impl Mutex {
     }
 
     #[inline]
-    pub fn take(&self, time: i32) -> Result<()> {
-        let err = unsafe { rt_mutex_take(self.raw, time) };
+    pub fn take(&self, time: impl Into<Timeout>) -> Result<()> {
+        let err = unsafe { rt_mutex_take(self.raw, time.into().to_raw()) };
         RtError::from_code_none(err, ())
     }
 
@@ -46,6 +48,15 @@ impl Mutex {
         let err = unsafe { rt_mutex_release(self.raw) };
         RtError::from_code_none(err, ())
     }
+
+    /// Whether the mutex is currently held by the calling thread
+    #[inline]
+    pub fn is_owned_by_current(&self) -> bool {
+        unsafe {
+            let owner = (*self.raw).owner;
+            !owner.is_null() && owner == rt_thread_self()
+        }
+    }
 }
 
 impl Object for Mutex {
@@ -81,7 +92,7 @@ impl MutexStatic {
         RtError::from_code_none(err, ())
     }
 
-    pub fn get(&'static self) -> Mutex {
+    pub fn get(&'static self, _ctx: ThreadContext) -> Mutex {
         Mutex {
             raw: self.raw.get().cast(),
         }
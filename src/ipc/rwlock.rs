@@ -0,0 +1,290 @@
+//! Reader-writer lock built from rt-thread mutexes and semaphores
+//!
+//! Writers are serialized by an [rt_mutex](super::mutex::Mutex), so priority inheritance
+//! still applies between writers. Readers only touch a small state protected by a
+//! [SpinLock](super::SpinLock) and sleep on semaphores when they have to wait.
+//!
+//! The lock prefers writers: once a writer is waiting, new readers are blocked until
+//! all the waiting writers have finished.
+
+use super::{mutex::Mutex as RawMutex, sem::Semaphore, IpcFlag, SpinLock};
+use crate::{
+    time::{Instant, Timeout},
+    Result, RtError,
+};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+struct State {
+    /// Readers holding the lock
+    readers: u32,
+    /// Readers sleeping on `gate`
+    readers_waiting: u32,
+    /// A writer holds the lock
+    writer: bool,
+    /// Writers which are waiting for `mutex` or for the readers to leave
+    writers_waiting: u32,
+    /// The writer holding `mutex` sleeps on `no_readers`
+    draining: bool,
+}
+
+/// A reader-writer lock protecting `T`, similar to `std::sync::RwLock`
+pub struct RwLock<T: ?Sized> {
+    mutex: RawMutex,
+    gate: Semaphore,
+    no_readers: Semaphore,
+    state: SpinLock<State>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+/// RAII guard of shared read access
+#[must_use = "if unused the RwLock will immediately unlock"]
+pub struct RwLockReadGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+}
+
+/// RAII guard of exclusive write access
+///
+/// The guard must be dropped by the thread which owns the underlying mutex.
+#[must_use = "if unused the RwLock will immediately unlock"]
+pub struct RwLockWriteGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+/// Time left from `time` after `start`
+#[inline]
+fn remaining(time: Timeout, start: Instant) -> Timeout {
+    match time {
+        Timeout::After(duration) => Timeout::until(start + duration),
+        other => other,
+    }
+}
+
+impl<T> RwLock<T> {
+    pub fn create(name: &str, flag: IpcFlag, data: T) -> Result<Self> {
+        let mutex = RawMutex::create(name, flag)?;
+        let gate = Semaphore::create(name, 0, flag).map_err(|err| {
+            mutex.delete().ok();
+            err
+        })?;
+        let no_readers = Semaphore::create(name, 0, flag).map_err(|err| {
+            mutex.delete().ok();
+            gate.delete().ok();
+            err
+        })?;
+        Ok(Self {
+            mutex,
+            gate,
+            no_readers,
+            state: SpinLock::new(State {
+                readers: 0,
+                readers_waiting: 0,
+                writer: false,
+                writers_waiting: 0,
+                draining: false,
+            }),
+            data: UnsafeCell::new(data),
+        })
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Acquire shared read access, blocking until no writer holds or waits for the lock
+    #[inline]
+    pub fn read(&self) -> Result<RwLockReadGuard<'_, T>> {
+        self.read_timeout(Timeout::Forever)
+    }
+
+    #[inline]
+    pub fn try_read(&self) -> Result<RwLockReadGuard<'_, T>> {
+        self.read_timeout(Timeout::NoWait)
+    }
+
+    /// Acquire shared read access, waiting at most `time`
+    pub fn read_timeout(&self, time: impl Into<Timeout>) -> Result<RwLockReadGuard<'_, T>> {
+        {
+            let mut state = self.state.lock();
+            if !state.writer && state.writers_waiting == 0 {
+                state.readers += 1;
+                return Ok(RwLockReadGuard { lock: self });
+            }
+            state.readers_waiting += 1;
+        }
+
+        // The writer admitting us has already counted us in `readers`
+        let mut gate = self.gate;
+        if let Err(err) = gate.take(time) {
+            let mut state = self.state.lock();
+            if gate.try_take().is_err() {
+                state.readers_waiting -= 1;
+                return Err(err);
+            }
+        }
+        Ok(RwLockReadGuard { lock: self })
+    }
+
+    /// Acquire exclusive write access, blocking until all readers have left
+    #[inline]
+    pub fn write(&self) -> Result<RwLockWriteGuard<'_, T>> {
+        self.write_timeout(Timeout::Forever)
+    }
+
+    #[inline]
+    pub fn try_write(&self) -> Result<RwLockWriteGuard<'_, T>> {
+        self.write_timeout(Timeout::NoWait)
+    }
+
+    /// Acquire exclusive write access, waiting at most `time` in total
+    ///
+    /// Fails with `RtError::Busy` if the current thread already holds the write lock.
+    pub fn write_timeout(&self, time: impl Into<Timeout>) -> Result<RwLockWriteGuard<'_, T>> {
+        if self.mutex.is_owned_by_current() {
+            return Err(RtError::Busy);
+        }
+        let time = time.into();
+        let start = Instant::now();
+        self.state.lock().writers_waiting += 1;
+
+        if let Err(err) = self.mutex.take(time) {
+            let mut state = self.state.lock();
+            state.writers_waiting -= 1;
+            if !state.writer {
+                self.admit_readers(&mut state);
+            }
+            return Err(err);
+        }
+
+        {
+            let mut state = self.state.lock();
+            if state.readers == 0 {
+                state.writers_waiting -= 1;
+                state.writer = true;
+                return Ok(RwLockWriteGuard {
+                    lock: self,
+                    _not_send: PhantomData,
+                });
+            }
+            state.draining = true;
+        }
+
+        let mut no_readers = self.no_readers;
+        if let Err(err) = no_readers.take(remaining(time, start)) {
+            let mut state = self.state.lock();
+            if no_readers.try_take().is_err() {
+                state.draining = false;
+                state.writers_waiting -= 1;
+                self.admit_readers(&mut state);
+                drop(state);
+                self.mutex.release().ok();
+                return Err(err);
+            }
+        }
+
+        let mut state = self.state.lock();
+        state.writers_waiting -= 1;
+        state.writer = true;
+        Ok(RwLockWriteGuard {
+            lock: self,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Wake up all the waiting readers if no writer is waiting
+    fn admit_readers(&self, state: &mut State) {
+        if state.writers_waiting > 0 {
+            return;
+        }
+        let mut gate = self.gate;
+        while state.readers_waiting > 0 {
+            state.readers_waiting -= 1;
+            state.readers += 1;
+            gate.release().ok();
+        }
+    }
+
+    fn read_unlock(&self) {
+        let mut state = self.state.lock();
+        state.readers -= 1;
+        if state.readers == 0 && state.draining {
+            state.draining = false;
+            let mut no_readers = self.no_readers;
+            no_readers.release().ok();
+        }
+    }
+
+    fn write_unlock(&self) {
+        {
+            let mut state = self.state.lock();
+            state.writer = false;
+            self.admit_readers(&mut state);
+        }
+        self.mutex.release().ok();
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        // We know statically that there are no other references to `self`, so
+        // there's no need to lock.
+        unsafe { &mut *self.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLock<T> {
+    fn drop(&mut self) {
+        self.mutex.delete().ok();
+        self.gate.delete().ok();
+        self.no_readers.delete().ok();
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RwLock").field("mutex", &self.mutex).finish()
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.read_unlock();
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.write_unlock();
+    }
+}
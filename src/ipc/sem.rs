@@ -5,7 +5,10 @@ use crate::{
         rt_object, rt_sem_create, rt_sem_delete, rt_sem_detach, rt_sem_init, rt_sem_release,
         rt_sem_t, rt_sem_take, rt_sem_trytake, rt_semaphore,
     },
+    isr::{IsrContext, IsrSemaphore},
     object::Object,
+    thread::ThreadContext,
+    time::Timeout,
     Result, RtError,
 };
 use core::{cell::UnsafeCell, marker::PhantomPinned, mem::MaybeUninit, ptr::NonNull};
@@ -34,8 +37,8 @@ impl Semaphore {
     }
 
     #[inline]
-    pub fn take(&mut self, time: i32) -> Result<()> {
-        let err = unsafe { rt_sem_take(self.raw, time) };
+    pub fn take(&mut self, time: impl Into<Timeout>) -> Result<()> {
+        let err = unsafe { rt_sem_take(self.raw, time.into().to_raw()) };
         RtError::from_code_none(err, ())
     }
 
@@ -85,9 +88,17 @@ impl SemaphoreStatic {
         RtError::from_code_none(err, ())
     }
 
-    pub fn get(&'static self) -> Semaphore {
+    pub fn get(&'static self, _ctx: ThreadContext) -> Semaphore {
         Semaphore {
             raw: self.raw.get().cast(),
         }
     }
+
+    /// Release-only view for interrupt handlers
+    #[inline]
+    pub fn get_isr(&'static self, _isr: IsrContext<'_>) -> IsrSemaphore {
+        IsrSemaphore::from(Semaphore {
+            raw: self.raw.get().cast(),
+        })
+    }
 }
@@ -0,0 +1,251 @@
+//! Interrupt context
+//!
+//! Code running in an interrupt handler, such as the callback of a hard timer, must
+//! not block and should not allocate. An [IsrContext](IsrContext) token proves that
+//! the code runs in interrupt context, and only the operations which are safe there
+//! take it:
+//!
+//! - [IsrSemaphore::release](IsrSemaphore::release)
+//! - [IsrEvent::send](IsrEvent::send)
+//! - [IsrMailbox::send](IsrMailbox::send)
+//! - [IsrMessageQueue::send](IsrMessageQueue::send) and
+//!   [IsrMessageQueue::send_urgent](IsrMessageQueue::send_urgent)
+//!
+//! Hard timer callbacks created by [Timer::create_isr](crate::timer::Timer::create_isr)
+//! are plain function pointers, so they cannot capture anything. Their only state is
+//! a value implementing [IsrSafe](IsrSafe), which the handles of blocking objects
+//! (mutexes, semaphores, threads, ...) do not. Their ISR views above do.
+//!
+//! ```ignore
+//! let sem = Semaphore::create("tick", 0, IpcFlag::Fifo)?;
+//! let timer = Timer::create_isr(
+//!     "tick",
+//!     IsrSemaphore::from(sem),
+//!     |sem, isr| {
+//!         sem.release(isr).ok();
+//!     },
+//!     10,
+//!     TimerFlags::PERIODIC | TimerFlags::HARD_TIMER,
+//! )?;
+//! ```
+//!
+//! ### Statics and free functions
+//! The blocking operations reachable without a handle, such as
+//! [Thread::delay](crate::thread::Thread::delay), [signal::wait](crate::signal::wait)
+//! or [thread::scope](crate::thread::scope), as well as
+//! [Thread::suspend](crate::thread::Thread::suspend) and the handles of `static`
+//! objects, take a [ThreadContext](crate::thread::ThreadContext) token instead. It
+//! cannot be captured by the callback nor stored in its state, and
+//! [ThreadContext::current](crate::thread::ThreadContext::current) fails in interrupt
+//! context. Interrupt handlers reach statics through their ISR views, e.g.
+//! [SemaphoreStatic::get_isr](crate::ipc::sem::SemaphoreStatic::get_isr).
+//!
+//! ### Limits
+//! Allocating from interrupt context is not rejected: the global allocator, and
+//! therefore `Box` or the creation of kernel objects, is still reachable. rt-thread
+//! reports it at run time if `RT_DEBUG_CONTEXT_CHECK` is enabled.
+
+use crate::{
+    ffi::rt_interrupt_get_nest,
+    ipc::{event::Event, mailbox::Mailbox, mq::MessageQueue, sem::Semaphore},
+    Result,
+};
+use core::{
+    marker::PhantomData,
+    sync::atomic::{
+        AtomicBool, AtomicI16, AtomicI32, AtomicI8, AtomicIsize, AtomicU16, AtomicU32, AtomicU8,
+        AtomicUsize,
+    },
+};
+
+/// Token proving that the code runs in interrupt context
+///
+/// The token cannot leave the interrupt handler it was given to, nor be sent to a
+/// thread.
+#[derive(Clone, Copy)]
+pub struct IsrContext<'a> {
+    _scope: PhantomData<&'a ()>,
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl IsrContext<'_> {
+    /// Create a token for the current interrupt handler
+    ///
+    /// ## Safety
+    ///
+    /// Must be called from an interrupt handler and the token must not be used after
+    /// the handler returns.
+    #[inline]
+    pub unsafe fn new() -> Self {
+        IsrContext {
+            _scope: PhantomData,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Interrupt nesting level, at least 1 in a handler which calls
+    /// `rt_interrupt_enter`
+    #[inline]
+    pub fn nest(&self) -> u8 {
+        unsafe { rt_interrupt_get_nest() }
+    }
+}
+
+/// Callback of a hard timer created by [Timer::create_isr](crate::timer::Timer::create_isr)
+///
+/// A function pointer rather than a closure, so that everything it touches other than
+/// statics comes through `state` or the token.
+pub type IsrEntry<S> = for<'a> fn(&mut S, IsrContext<'a>);
+
+/// Whether the code runs in an interrupt handler
+#[inline]
+pub fn in_isr() -> bool {
+    unsafe { rt_interrupt_get_nest() > 0 }
+}
+
+/// Types which can be used from interrupt context
+///
+/// ## Safety
+///
+/// No method of the type may block. Handles of objects whose methods may block must
+/// not implement this trait, even if they are `Send`.
+pub unsafe trait IsrSafe {}
+
+unsafe impl IsrSafe for () {}
+unsafe impl IsrSafe for bool {}
+unsafe impl IsrSafe for char {}
+unsafe impl IsrSafe for u8 {}
+unsafe impl IsrSafe for u16 {}
+unsafe impl IsrSafe for u32 {}
+unsafe impl IsrSafe for u64 {}
+unsafe impl IsrSafe for usize {}
+unsafe impl IsrSafe for i8 {}
+unsafe impl IsrSafe for i16 {}
+unsafe impl IsrSafe for i32 {}
+unsafe impl IsrSafe for i64 {}
+unsafe impl IsrSafe for isize {}
+unsafe impl IsrSafe for f32 {}
+unsafe impl IsrSafe for f64 {}
+unsafe impl IsrSafe for AtomicBool {}
+unsafe impl IsrSafe for AtomicU8 {}
+unsafe impl IsrSafe for AtomicU16 {}
+unsafe impl IsrSafe for AtomicU32 {}
+unsafe impl IsrSafe for AtomicUsize {}
+unsafe impl IsrSafe for AtomicI8 {}
+unsafe impl IsrSafe for AtomicI16 {}
+unsafe impl IsrSafe for AtomicI32 {}
+unsafe impl IsrSafe for AtomicIsize {}
+unsafe impl<T: IsrSafe> IsrSafe for Option<T> {}
+unsafe impl<T: IsrSafe, const N: usize> IsrSafe for [T; N] {}
+/// Shared with threads, e.g. a `static` counter
+unsafe impl<T: IsrSafe + Sync> IsrSafe for &'static T {}
+unsafe impl<A: IsrSafe, B: IsrSafe> IsrSafe for (A, B) {}
+unsafe impl<A: IsrSafe, B: IsrSafe, C: IsrSafe> IsrSafe for (A, B, C) {}
+unsafe impl<A: IsrSafe, B: IsrSafe, C: IsrSafe, D: IsrSafe> IsrSafe for (A, B, C, D) {}
+
+/// Release-only view of a [Semaphore](Semaphore)
+#[derive(Clone, Copy)]
+pub struct IsrSemaphore {
+    sem: Semaphore,
+}
+
+unsafe impl Send for IsrSemaphore {}
+unsafe impl IsrSafe for IsrSemaphore {}
+
+impl IsrSemaphore {
+    #[inline]
+    pub fn release(&self, _isr: IsrContext<'_>) -> Result<()> {
+        let mut sem = self.sem;
+        sem.release()
+    }
+}
+
+impl From<Semaphore> for IsrSemaphore {
+    #[inline]
+    fn from(sem: Semaphore) -> Self {
+        IsrSemaphore { sem }
+    }
+}
+
+/// Send-only view of an [Event](Event)
+#[derive(Clone, Copy)]
+pub struct IsrEvent {
+    event: Event,
+}
+
+unsafe impl Send for IsrEvent {}
+unsafe impl IsrSafe for IsrEvent {}
+
+impl IsrEvent {
+    #[inline]
+    pub fn send(&self, set: u32, _isr: IsrContext<'_>) -> Result<()> {
+        self.event.send(set)
+    }
+}
+
+impl From<Event> for IsrEvent {
+    #[inline]
+    fn from(event: Event) -> Self {
+        IsrEvent { event }
+    }
+}
+
+/// Non-blocking send-only view of a [Mailbox](Mailbox)
+#[derive(Clone, Copy)]
+pub struct IsrMailbox {
+    mailbox: Mailbox,
+}
+
+unsafe impl Send for IsrMailbox {}
+unsafe impl IsrSafe for IsrMailbox {}
+
+impl IsrMailbox {
+    /// Fails with `RtError::Full` instead of waiting if the mailbox is full
+    #[inline]
+    pub fn send(&self, value: usize, _isr: IsrContext<'_>) -> Result<()> {
+        self.mailbox.send(value)
+    }
+}
+
+impl From<Mailbox> for IsrMailbox {
+    #[inline]
+    fn from(mailbox: Mailbox) -> Self {
+        IsrMailbox { mailbox }
+    }
+}
+
+/// Non-blocking send-only view of a [MessageQueue](MessageQueue)
+pub struct IsrMessageQueue<T> {
+    mq: MessageQueue<T>,
+}
+
+impl<T> Clone for IsrMessageQueue<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for IsrMessageQueue<T> {}
+
+unsafe impl<T: Send> Send for IsrMessageQueue<T> {}
+unsafe impl<T: Send> IsrSafe for IsrMessageQueue<T> {}
+
+impl<T: Copy + Send> IsrMessageQueue<T> {
+    /// Fails with `RtError::Full` instead of waiting if the queue is full
+    #[inline]
+    pub fn send(&self, msg: T, _isr: IsrContext<'_>) -> Result<()> {
+        self.mq.send(msg)
+    }
+
+    #[inline]
+    pub fn send_urgent(&self, msg: T, _isr: IsrContext<'_>) -> Result<()> {
+        self.mq.send_urgent(msg)
+    }
+}
+
+impl<T> From<MessageQueue<T>> for IsrMessageQueue<T> {
+    #[inline]
+    fn from(mq: MessageQueue<T>) -> Self {
+        IsrMessageQueue { mq }
+    }
+}
@@ -2,17 +2,12 @@
 //!
 //! Rust wrapper for [rt-thread](https://github.com/RT-Thread/rt-thread/tree/master)
 
-//! ### TODO
-//! 1. communication
-//!     1. rt_mailbox
-//!     1. rt_messagequeue
-//!     1. rt_signal
-//!
-
 #![cfg_attr(not(test), no_std)]
 
 #![feature(clamp)]
+#![feature(min_const_generics)]
 #![feature(alloc_error_handler)]
+#![cfg_attr(feature = "allocator-api", feature(allocator_api))]
 #![cfg_attr(test, feature(proc_macro_hygiene))]
 
 #[cfg(test)]
@@ -45,8 +40,16 @@ pub mod device;
 pub mod ffi;
 pub mod fmt;
 pub mod ipc;
+pub mod isr;
+#[cfg(feature = "memheap")]
+pub mod memheap;
+pub mod mempool;
 pub mod object;
+pub mod panic;
+#[cfg(feature = "signal")]
+pub mod signal;
 pub mod thread;
+pub mod time;
 pub mod timer;
 
 #[cfg(test)]
@@ -60,6 +63,8 @@ pub mod io;
 
 #[cfg(all(not(test), not(feature = "custom-panic")))]
 use core::{fmt::Write, panic::PanicInfo};
+#[cfg(all(not(test), not(feature = "custom-panic")))]
+use object::Object;
 use ffi::rt_err_t;
 
 #[macro_export]
@@ -81,14 +86,23 @@ macro_rules! print {
 #[cfg_attr(not(test), panic_handler)]
 fn panic(panic: &PanicInfo<'_>) -> ! {
     let mut writer = fmt::Console {};
-    writeln!(writer, "{}", panic).ok();
-    loop {}
+    match crate::panic::current_thread() {
+        Some(thread) => writeln!(
+            writer,
+            "thread '{}' {}",
+            thread.get_name().as_ref().trim_end_matches('\0'),
+            panic
+        ),
+        None => writeln!(writer, "{}", panic),
+    }
+    .ok();
+    crate::panic::handle(panic)
 }
 
 #[cfg(all(not(test), feature = "alloc"))]
 #[cfg_attr(not(test), alloc_error_handler)]
 fn alloc_error_handler(layout: alloc::alloc::Layout) -> ! {
-    panic!("allocation error: {:?}", layout)
+    allocator::handle_alloc_error(layout)
 }
 
 /// RT-Thread error code definitions
@@ -0,0 +1,283 @@
+//! memheap manages several discontinuous memory regions, each one as a separate heap.
+//!
+//! A [MemHeap](MemHeap) is usually placed in a `static` item and initialized with a
+//! region such as CCM RAM or external SDRAM. With the `allocator-api` feature it
+//! implements `core::alloc::Allocator`, so collections can be placed in a specific
+//! region:
+//!
+//! ```ignore
+//! static SDRAM_HEAP: MemHeap = MemHeap::new();
+//!
+//! unsafe { SDRAM_HEAP.init("sdram", 0xC000_0000 as *mut u8, 8 * 1024 * 1024)? };
+//! let mut samples: Vec<u16, _> = Vec::new_in(&SDRAM_HEAP);
+//! ```
+//!
+//! rt-thread must be configured with `RT_USING_MEMHEAP`.
+
+use crate::{
+    cstr::RtName,
+    ffi::{
+        rt_memheap, rt_memheap_alloc, rt_memheap_detach, rt_memheap_free, rt_memheap_init,
+        rt_memheap_realloc, rt_object, rt_size_t, RT_ALIGN_SIZE,
+    },
+    object::Object,
+    Result, RtError,
+};
+use core::{
+    alloc::Layout,
+    cell::UnsafeCell,
+    marker::PhantomPinned,
+    mem::{size_of, MaybeUninit},
+    ptr::{null_mut, NonNull},
+};
+
+/// A heap managing one memory region
+pub struct MemHeap {
+    raw: UnsafeCell<MaybeUninit<rt_memheap>>,
+    _pinned: PhantomPinned,
+}
+
+unsafe impl Send for MemHeap {}
+unsafe impl Sync for MemHeap {}
+
+/// Whether rt-thread already guarantees the alignment of `layout`
+#[inline]
+fn is_natural(layout: &Layout) -> bool {
+    layout.align() <= RT_ALIGN_SIZE as usize
+}
+
+/// Size to allocate for an over-aligned `layout`, leaving room for the alignment and
+/// a header holding the real allocation
+#[inline]
+fn over_aligned_size(layout: &Layout) -> Option<usize> {
+    layout
+        .size()
+        .checked_add(layout.align())
+        .and_then(|size| size.checked_add(size_of::<usize>()))
+}
+
+/// Start of the user data in the real allocation at `raw`, the header is right before
+#[inline]
+fn over_aligned_start(raw: usize, layout: &Layout) -> usize {
+    (raw + size_of::<usize>() + layout.align() - 1) & !(layout.align() - 1)
+}
+
+impl MemHeap {
+    pub const fn new() -> Self {
+        MemHeap {
+            raw: UnsafeCell::new(MaybeUninit::uninit()),
+            _pinned: PhantomPinned {},
+        }
+    }
+
+    ///
+    /// This function initializes a piece of memory called memheap.
+    ///
+    /// @param name the name of the memheap
+    /// @param start the start address of the memory region
+    /// @param size the size of the memory region
+    ///
+    /// @return RT_EOK
+    ///
+    /// ## Safety
+    ///
+    /// The region must be valid memory which is not used by anything else.
+    ///
+    pub unsafe fn init(&'static self, name: &str, start: *mut u8, size: usize) -> Result<()> {
+        let name: RtName = name.into();
+        let err = rt_memheap_init(
+            self.raw.get().cast(),
+            name.into(),
+            start.cast(),
+            size as rt_size_t,
+        );
+        RtError::from_code_none(err, ())
+    }
+
+    /// Initialize the heap on a statically allocated buffer
+    #[inline]
+    pub fn init_with(&'static self, name: &str, buffer: &'static mut [u8]) -> Result<()> {
+        unsafe { self.init(name, buffer.as_mut_ptr(), buffer.len()) }
+    }
+
+    /// Detach the heap
+    ///
+    /// ## Safety
+    ///
+    /// No memory allocated from the heap shall be in use.
+    #[inline]
+    pub unsafe fn detach(&'static self) -> Result<()> {
+        let err = rt_memheap_detach(self.raw.get().cast());
+        RtError::from_code_none(err, ())
+    }
+
+    #[inline]
+    fn get_raw(&self) -> *mut rt_memheap {
+        self.raw.get().cast()
+    }
+
+    /// Allocate `size` bytes aligned to `RT_ALIGN_SIZE`, return null on failure
+    #[inline]
+    pub fn malloc(&self, size: usize) -> *mut u8 {
+        unsafe { rt_memheap_alloc(self.get_raw(), size as rt_size_t).cast() }
+    }
+
+    /// Free memory allocated by [malloc](MemHeap::malloc) or [realloc](MemHeap::realloc)
+    /// of any memheap
+    ///
+    /// ## Safety
+    ///
+    /// `ptr` must be allocated from a memheap and not freed yet.
+    #[inline]
+    pub unsafe fn free(ptr: *mut u8) {
+        rt_memheap_free(ptr.cast())
+    }
+
+    /// Resize memory allocated by [malloc](MemHeap::malloc), return null on failure
+    ///
+    /// ## Safety
+    ///
+    /// `ptr` must be allocated from this heap and not freed yet.
+    #[inline]
+    pub unsafe fn realloc(&self, ptr: *mut u8, size: usize) -> *mut u8 {
+        rt_memheap_realloc(self.get_raw(), ptr.cast(), size as rt_size_t).cast()
+    }
+
+    /// Allocate memory for `layout`, return null on failure
+    ///
+    /// Alignments larger than `RT_ALIGN_SIZE` are handled by over-allocating and
+    /// storing the original pointer right before the returned one. Layouts whose padded
+    /// size overflows `usize` fail.
+    pub fn alloc(&self, layout: Layout) -> *mut u8 {
+        if is_natural(&layout) {
+            return self.malloc(layout.size());
+        }
+        let size = match over_aligned_size(&layout) {
+            Some(size) => size,
+            None => return null_mut(),
+        };
+        let raw = self.malloc(size);
+        if raw.is_null() {
+            return null_mut();
+        }
+        let aligned = over_aligned_start(raw as usize, &layout);
+        unsafe { ((aligned - size_of::<usize>()) as *mut usize).write(raw as usize) };
+        aligned as *mut u8
+    }
+
+    /// Free memory allocated by [alloc](MemHeap::alloc)
+    ///
+    /// ## Safety
+    ///
+    /// `ptr` must be allocated by [alloc](MemHeap::alloc) with the same `layout`.
+    pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        if is_natural(&layout) {
+            Self::free(ptr)
+        } else {
+            let raw = ((ptr as usize - size_of::<usize>()) as *const usize).read();
+            Self::free(raw as *mut u8)
+        }
+    }
+}
+
+impl Object for MemHeap {
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        NonNull::new(self.get_raw().cast()).expect("Unexpected null rt_memheap")
+    }
+}
+
+#[cfg(feature = "allocator-api")]
+mod allocator_api {
+    use super::{is_natural, MemHeap};
+    use core::{
+        alloc::{AllocError, Allocator, Layout},
+        ptr::{self, NonNull},
+    };
+
+    #[inline]
+    fn slice(ptr: *mut u8, size: usize) -> Result<NonNull<[u8]>, AllocError> {
+        NonNull::new(ptr::slice_from_raw_parts_mut(ptr, size)).ok_or(AllocError)
+    }
+
+    unsafe impl Allocator for MemHeap {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            if layout.size() == 0 {
+                return slice(layout.align() as *mut u8, 0);
+            }
+            slice(self.alloc(layout), layout.size())
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            if layout.size() != 0 {
+                MemHeap::dealloc(ptr.as_ptr(), layout)
+            }
+        }
+
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            if old_layout.size() != 0
+                && old_layout.align() == new_layout.align()
+                && is_natural(&new_layout)
+            {
+                return slice(
+                    self.realloc(ptr.as_ptr(), new_layout.size()),
+                    new_layout.size(),
+                );
+            }
+            let new = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr().cast(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+            Ok(new)
+        }
+
+        unsafe fn shrink(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            if new_layout.size() != 0
+                && old_layout.align() == new_layout.align()
+                && is_natural(&new_layout)
+            {
+                return slice(
+                    self.realloc(ptr.as_ptr(), new_layout.size()),
+                    new_layout.size(),
+                );
+            }
+            let new = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new.as_ptr().cast(), new_layout.size());
+            self.deallocate(ptr, old_layout);
+            Ok(new)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn over_aligned_header_fits() {
+        let header = size_of::<usize>();
+        for &align in &[8, 16, 32, 64, 256] {
+            for &size in &[1, 3, 8, 100] {
+                let layout = Layout::from_size_align(size, align).unwrap();
+                assert!(!is_natural(&layout));
+                let total = over_aligned_size(&layout).unwrap();
+                // rt-thread only aligns blocks to RT_ALIGN_SIZE
+                let heap = 0x2000_0000;
+                for raw in (heap..heap + 2 * align).step_by(RT_ALIGN_SIZE as usize) {
+                    let start = over_aligned_start(raw, &layout);
+                    assert_eq!(start % align, 0);
+                    assert!(start - header >= raw);
+                    assert!(start + size <= raw + total);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! Memory pool is a memory allocation method for allocating blocks of the same size.
+//!
+//! Allocating from a pool takes constant time. When the pool is empty, the allocating
+//! thread can be suspended until another thread frees a block or the waiting time runs
+//! out.
+//!
+//! [Pool](Pool) hands out [PoolBox](PoolBox) smart pointers which return their block to
+//! the pool when dropped.
+
+use crate::{
+    cstr::RtName,
+    ffi::{
+        rt_mempool, rt_mp_alloc, rt_mp_create, rt_mp_delete, rt_mp_detach, rt_mp_free, rt_mp_init,
+        rt_mp_t, rt_object, rt_size_t, RT_ALIGN_SIZE,
+    },
+    object::Object,
+    thread::ThreadContext,
+    time::Timeout,
+    Result, RtError,
+};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    marker::{PhantomData, PhantomPinned},
+    mem::{align_of, size_of, MaybeUninit},
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+#[derive(Copy, Clone, Debug)]
+pub struct MemPool {
+    raw: rt_mp_t,
+}
+
+unsafe impl Send for MemPool {}
+unsafe impl Sync for MemPool {}
+
+impl MemPool {
+    ///
+    /// This function will create a mempool object and allocate the memory pool from
+    /// heap.
+    ///
+    /// @param name the name of memory pool
+    /// @param block_count the count of blocks in memory pool
+    /// @param block_size the size for each block
+    ///
+    /// @return the created mempool object
+    ///
+    pub fn create(name: &str, block_count: usize, block_size: usize) -> Result<Self> {
+        let name: RtName = name.into();
+        let result = unsafe {
+            rt_mp_create(
+                name.into(),
+                block_count as rt_size_t,
+                block_size as rt_size_t,
+            )
+        };
+        if result.is_null() {
+            Err(RtError::Error)
+        } else {
+            Ok(MemPool { raw: result })
+        }
+    }
+
+    ///
+    /// This function will delete a memory pool and release the object memory.
+    ///
+    /// @return RT_EOK
+    ///
+    #[inline]
+    pub fn delete(self) -> Result<()> {
+        let err = unsafe { rt_mp_delete(self.raw) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will allocate a block from memory pool
+    ///
+    /// @param time the waiting time
+    ///
+    /// @return the allocated memory block
+    ///
+    #[inline]
+    pub fn alloc(&self, time: impl Into<Timeout>) -> Result<NonNull<u8>> {
+        let block = unsafe { rt_mp_alloc(self.raw, time.into().to_raw()) };
+        NonNull::new(block.cast()).ok_or(RtError::NoMem)
+    }
+
+    ///
+    /// This function will release a memory block
+    ///
+    /// ## Safety
+    ///
+    /// `block` must be allocated by [alloc](MemPool::alloc) and not freed yet.
+    ///
+    #[inline]
+    pub unsafe fn free(block: NonNull<u8>) {
+        rt_mp_free(block.as_ptr().cast())
+    }
+
+    /// Size of each block
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        unsafe { (*self.raw).block_size as usize }
+    }
+
+    /// Number of blocks in the pool
+    #[inline]
+    pub fn block_count(&self) -> usize {
+        unsafe { (*self.raw).block_total_count as usize }
+    }
+
+    /// Number of free blocks in the pool
+    #[inline]
+    pub fn free_count(&self) -> usize {
+        unsafe { (*self.raw).block_free_count as usize }
+    }
+}
+
+impl Object for MemPool {
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        NonNull::new(self.raw.cast()).expect("Unexpected null rt_mp_t")
+    }
+}
+
+/// Statically allocated memory pool with a buffer of `SIZE` words (`usize`)
+///
+/// Every block takes `block_size` plus the size of a pointer from the buffer.
+pub struct MemPoolStatic<const SIZE: usize> {
+    raw: UnsafeCell<MaybeUninit<rt_mempool>>,
+    pool: UnsafeCell<MaybeUninit<[usize; SIZE]>>,
+    _pinned: PhantomPinned,
+}
+
+unsafe impl<const SIZE: usize> Send for MemPoolStatic<SIZE> {}
+unsafe impl<const SIZE: usize> Sync for MemPoolStatic<SIZE> {}
+
+impl<const SIZE: usize> MemPoolStatic<SIZE> {
+    pub const fn new() -> Self {
+        MemPoolStatic {
+            raw: UnsafeCell::new(MaybeUninit::uninit()),
+            pool: UnsafeCell::new(MaybeUninit::uninit()),
+            _pinned: PhantomPinned {},
+        }
+    }
+
+    pub fn init(&'static self, name: &str, block_size: usize) -> Result<()> {
+        let name: RtName = name.into();
+        let err = unsafe {
+            rt_mp_init(
+                self.raw.get().cast(),
+                name.into(),
+                self.pool.get().cast(),
+                size_of::<[usize; SIZE]>() as rt_size_t,
+                block_size as rt_size_t,
+            )
+        };
+        RtError::from_code_none(err, ())
+    }
+
+    #[inline]
+    pub fn detach(&'static self) -> Result<()> {
+        let err = unsafe { rt_mp_detach(self.raw.get().cast()) };
+        RtError::from_code_none(err, ())
+    }
+
+    pub fn get(&'static self, _ctx: ThreadContext) -> MemPool {
+        MemPool {
+            raw: self.raw.get().cast(),
+        }
+    }
+}
+
+/// Blocks handed out by rt-thread are only aligned to the block header
+#[inline]
+fn check_layout<T>() -> Result<()> {
+    if align_of::<T>() <= (RT_ALIGN_SIZE as usize).min(align_of::<*mut u8>()) {
+        Ok(())
+    } else {
+        Err(RtError::Inval)
+    }
+}
+
+/// Memory pool of blocks holding `T`
+///
+/// The pool is deleted when dropped, which is only possible after all the
+/// [PoolBox](PoolBox) have been dropped.
+pub struct Pool<T> {
+    mp: MemPool,
+    _value: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for Pool<T> {}
+unsafe impl<T: Send> Sync for Pool<T> {}
+
+impl<T> Pool<T> {
+    /// Create a pool of `count` blocks
+    ///
+    /// Fails with `RtError::Inval` if the alignment of `T` is larger than the one
+    /// guaranteed by rt-thread.
+    pub fn create(name: &str, count: usize) -> Result<Self> {
+        check_layout::<T>()?;
+        Ok(Self {
+            mp: MemPool::create(name, count, size_of::<T>())?,
+            _value: PhantomData,
+        })
+    }
+
+    /// Move `value` into a block, waiting for a free block at most `time`
+    pub fn alloc_timeout(&self, value: T, time: impl Into<Timeout>) -> Result<PoolBox<'_, T>> {
+        alloc(self.mp, value, time.into())
+    }
+
+    /// Move `value` into a block, waiting until a block is freed
+    #[inline]
+    pub fn alloc(&self, value: T) -> Result<PoolBox<'_, T>> {
+        self.alloc_timeout(value, Timeout::Forever)
+    }
+
+    /// Move `value` into a block without waiting
+    #[inline]
+    pub fn try_alloc(&self, value: T) -> Result<PoolBox<'_, T>> {
+        self.alloc_timeout(value, Timeout::NoWait)
+    }
+
+    /// Get the untyped memory pool
+    #[inline]
+    pub fn raw(&self) -> MemPool {
+        self.mp
+    }
+}
+
+impl<T> Drop for Pool<T> {
+    fn drop(&mut self) {
+        self.mp.delete().ok();
+    }
+}
+
+#[inline]
+fn alloc<'a, T>(mp: MemPool, value: T, time: Timeout) -> Result<PoolBox<'a, T>> {
+    let block = mp.alloc(time)?.cast::<T>();
+    unsafe { block.as_ptr().write(value) };
+    Ok(PoolBox {
+        ptr: block,
+        _pool: PhantomData,
+    })
+}
+
+/// Statically allocated [Pool](Pool) of `N` blocks holding `T`
+pub struct PoolStatic<T, const N: usize> {
+    raw: UnsafeCell<MaybeUninit<rt_mempool>>,
+    pool: UnsafeCell<MaybeUninit<[PoolSlot<T>; N]>>,
+    _pinned: PhantomPinned,
+}
+
+/// Layout of a block in the pool buffer: a header pointing back to the pool followed
+/// by the value
+#[repr(C)]
+struct PoolSlot<T> {
+    header: *mut u8,
+    block: MaybeUninit<T>,
+}
+
+unsafe impl<T: Send, const N: usize> Send for PoolStatic<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for PoolStatic<T, N> {}
+
+impl<T, const N: usize> PoolStatic<T, N> {
+    pub const fn new() -> Self {
+        PoolStatic {
+            raw: UnsafeCell::new(MaybeUninit::uninit()),
+            pool: UnsafeCell::new(MaybeUninit::uninit()),
+            _pinned: PhantomPinned {},
+        }
+    }
+
+    pub fn init(&'static self, name: &str) -> Result<()> {
+        check_layout::<T>()?;
+        let name: RtName = name.into();
+        let err = unsafe {
+            rt_mp_init(
+                self.raw.get().cast(),
+                name.into(),
+                self.pool.get().cast(),
+                size_of::<[PoolSlot<T>; N]>() as rt_size_t,
+                size_of::<T>() as rt_size_t,
+            )
+        };
+        RtError::from_code_none(err, ())
+    }
+
+    /// Detach the pool, no [PoolBox](PoolBox) of it shall be alive
+    #[inline]
+    pub unsafe fn detach(&'static self) -> Result<()> {
+        let err = rt_mp_detach(self.raw.get().cast());
+        RtError::from_code_none(err, ())
+    }
+
+    /// Waiting for a free block takes a [ThreadContext](ThreadContext),
+    /// [try_alloc](PoolStatic::try_alloc) does not wait
+    pub fn alloc_timeout(
+        &'static self,
+        value: T,
+        time: impl Into<Timeout>,
+        _ctx: ThreadContext,
+    ) -> Result<PoolBox<'static, T>> {
+        alloc(self.mp(), value, time.into())
+    }
+
+    #[inline]
+    pub fn alloc(&'static self, value: T, ctx: ThreadContext) -> Result<PoolBox<'static, T>> {
+        self.alloc_timeout(value, Timeout::Forever, ctx)
+    }
+
+    #[inline]
+    pub fn try_alloc(&'static self, value: T) -> Result<PoolBox<'static, T>> {
+        alloc(self.mp(), value, Timeout::NoWait)
+    }
+
+    /// Get the untyped memory pool
+    #[inline]
+    pub fn raw(&'static self, _ctx: ThreadContext) -> MemPool {
+        self.mp()
+    }
+
+    #[inline]
+    fn mp(&'static self) -> MemPool {
+        MemPool {
+            raw: self.raw.get().cast(),
+        }
+    }
+}
+
+/// A pointer to `T` stored in a memory pool block, the block is returned to the pool
+/// on drop
+pub struct PoolBox<'a, T> {
+    ptr: NonNull<T>,
+    _pool: PhantomData<(&'a Pool<T>, T)>,
+}
+
+unsafe impl<T: Send> Send for PoolBox<'_, T> {}
+unsafe impl<T: Sync> Sync for PoolBox<'_, T> {}
+
+impl<'a, T> PoolBox<'a, T> {
+    /// Consume the box without freeing the block
+    #[inline]
+    pub fn into_raw(b: Self) -> NonNull<T> {
+        let ptr = b.ptr;
+        core::mem::forget(b);
+        ptr
+    }
+
+    /// Rebuild a box from [into_raw](PoolBox::into_raw)
+    ///
+    /// ## Safety
+    ///
+    /// `ptr` must come from [into_raw](PoolBox::into_raw) of a box of a pool living
+    /// for `'a`.
+    #[inline]
+    pub unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        Self {
+            ptr,
+            _pool: PhantomData,
+        }
+    }
+
+    /// Move the value out and free the block
+    #[inline]
+    pub fn into_inner(b: Self) -> T {
+        let ptr = Self::into_raw(b);
+        unsafe {
+            let value = ptr.as_ptr().read();
+            MemPool::free(ptr.cast());
+            value
+        }
+    }
+}
+
+impl<T> Deref for PoolBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> DerefMut for PoolBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T> Drop for PoolBox<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            core::ptr::drop_in_place(self.ptr.as_ptr());
+            MemPool::free(self.ptr.cast());
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolBox<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Blocks rt-thread carves out of the buffer of a [PoolStatic](PoolStatic), each
+    /// one being the aligned block size plus a header pointer
+    fn capacity<T, const N: usize>() -> usize {
+        let align = RT_ALIGN_SIZE as usize;
+        let block_size = (size_of::<T>() + align - 1) / align * align;
+        size_of::<[PoolSlot<T>; N]>() / (block_size + size_of::<*mut u8>())
+    }
+
+    fn check<T, const N: usize>() {
+        assert!(check_layout::<T>().is_ok());
+        assert!(capacity::<T, N>() >= N);
+    }
+
+    #[test]
+    fn static_pool_holds_n_blocks() {
+        check::<u8, 1>();
+        check::<u8, 7>();
+        check::<u16, 3>();
+        check::<u32, 5>();
+        check::<[u8; 3], 4>();
+        check::<[u8; 5], 9>();
+        check::<(u32, u8), 6>();
+        check::<[u32; 16], 2>();
+    }
+}
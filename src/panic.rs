@@ -0,0 +1,170 @@
+//! Panic policy of the default panic handler
+//!
+//! By default a panicking thread spins forever at its priority, which starves every
+//! thread of lower priority. The policy can be changed to suspend only the panicking
+//! thread or to reboot the board, and a supervisor can be notified of the panic.
+//!
+//! ```ignore
+//! panic::set_supervisor(|thread, info| log_fault(thread, info));
+//! panic::set_policy(PanicPolicy::SuspendThread);
+//! ```
+//!
+//! With the `custom-panic` feature, a custom `panic_handler` can still apply the
+//! policy by calling [handle](handle).
+
+use crate::{
+    ffi::{rt_critical_level, rt_schedule, rt_thread_delay, RT_TICK_MAX},
+    ipc::SpinLock,
+    isr::in_isr,
+    object::Object,
+    thread::{Thread, ThreadContext},
+};
+use core::{
+    panic::PanicInfo,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// What happens to the system after a panic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Spin forever in the panicking context
+    Halt,
+    /// Suspend and mark the panicking thread, the rest of the system keeps running
+    ///
+    /// Panics in interrupt context, before the scheduler starts or while the scheduler
+    /// is locked, e.g. inside a [SpinLock](crate::ipc::SpinLock), cannot switch away
+    /// from the thread. They reboot if a hook is set and halt otherwise.
+    SuspendThread,
+    /// Call the hook set by [set_reboot_hook](set_reboot_hook), halt if there is none
+    Reboot,
+}
+
+/// Callback notified of panics with the name of the panicking thread
+pub type Supervisor = fn(&str, &PanicInfo<'_>);
+
+/// Board specific reset
+pub type RebootHook = fn() -> !;
+
+#[derive(Clone, Copy)]
+struct Config {
+    policy: PanicPolicy,
+    supervisor: Option<Supervisor>,
+    reboot: Option<RebootHook>,
+}
+
+const DEFAULT_CONFIG: Config = Config {
+    policy: PanicPolicy::Halt,
+    supervisor: None,
+    reboot: None,
+};
+
+static CONFIG: SpinLock<Config> = SpinLock::new(DEFAULT_CONFIG);
+/// Set while [CONFIG](CONFIG) is locked, so that a panic inside does not reenter it
+static CONFIG_LOCKED: AtomicBool = AtomicBool::new(false);
+
+fn with_config<R>(f: impl FnOnce(&mut Config) -> R) -> R {
+    let mut config = CONFIG.lock();
+    CONFIG_LOCKED.store(true, Ordering::Release);
+    let result = f(&mut config);
+    CONFIG_LOCKED.store(false, Ordering::Release);
+    result
+}
+
+/// Read the config, or the defaults if the panic happened while it was locked
+fn try_config() -> (PanicPolicy, Option<Supervisor>, Option<RebootHook>) {
+    // Another thread cannot hold the lock meanwhile, as it locks the scheduler
+    let config = if CONFIG_LOCKED.load(Ordering::Acquire) {
+        DEFAULT_CONFIG
+    } else {
+        *CONFIG.lock()
+    };
+    (config.policy, config.supervisor, config.reboot)
+}
+
+pub fn set_policy(policy: PanicPolicy) {
+    with_config(|config| config.policy = policy)
+}
+
+pub fn policy() -> PanicPolicy {
+    with_config(|config| config.policy)
+}
+
+/// Set the callback notified of every panic before the policy is applied
+///
+/// The supervisor runs in the panicking context and shall not panic.
+pub fn set_supervisor(supervisor: Supervisor) {
+    with_config(|config| config.supervisor = Some(supervisor))
+}
+
+pub fn set_reboot_hook(reboot: RebootHook) {
+    with_config(|config| config.reboot = Some(reboot))
+}
+
+/// The thread which panicked, if the panic happened in thread context
+pub fn current_thread() -> Option<Thread> {
+    if in_isr() {
+        None
+    } else {
+        Thread::current().ok()
+    }
+}
+
+fn halt() -> ! {
+    loop {}
+}
+
+fn reboot_or_halt(reboot: Option<RebootHook>) -> ! {
+    match reboot {
+        Some(reboot) => reboot(),
+        None => halt(),
+    }
+}
+
+/// Whether the scheduler can switch away from the panicking context
+fn can_schedule() -> bool {
+    !in_isr() && unsafe { rt_critical_level() } == 0
+}
+
+/// Remove the current thread from scheduling forever
+fn park(thread: Thread, ctx: ThreadContext) -> ! {
+    loop {
+        if thread.suspend(ctx).is_ok() {
+            unsafe { rt_schedule() };
+        } else {
+            // Sleeping also keeps the thread away from the CPU
+            unsafe { rt_thread_delay(RT_TICK_MAX / 2) };
+        }
+    }
+}
+
+/// Notify the supervisor and apply the panic policy
+pub fn handle(info: &PanicInfo<'_>) -> ! {
+    let (policy, supervisor, reboot) = try_config();
+    let thread = current_thread();
+
+    #[cfg(feature = "alloc")]
+    {
+        if let Some(thread) = thread {
+            if thread.mark_panicked() {
+                // Panicked again while handling the first panic
+                halt();
+            }
+        }
+    }
+
+    if let Some(supervisor) = supervisor {
+        match thread {
+            Some(thread) => supervisor(thread.get_name().as_ref().trim_end_matches('\0'), info),
+            None => supervisor("", info),
+        }
+    }
+
+    match (policy, thread) {
+        (PanicPolicy::SuspendThread, Some(thread)) if can_schedule() => {
+            // The current thread exists and the panic is not in interrupt context
+            park(thread, unsafe { ThreadContext::new() })
+        }
+        (PanicPolicy::SuspendThread, _) | (PanicPolicy::Reboot, _) => reboot_or_halt(reboot),
+        (PanicPolicy::Halt, _) => halt(),
+    }
+}
@@ -0,0 +1,151 @@
+//! Signal (also known as soft interrupt signal), from a software perspective,
+//! is a simulation of interrupt mechanism.
+//!
+//! A thread can install handlers for signals, and other threads can send signals
+//! to it with [Thread::kill](crate::thread::Thread::kill). The handler is executed
+//! the next time the target thread is scheduled.
+//!
+//! rt-thread must be configured with `RT_USING_SIGNALS`.
+//!
+//! ### Note
+//! Like rt-thread, handlers are installed per thread. Rust handlers are stored in the
+//! Rust slot of the thread (`rt_thread.user_data`) and dispatched through a single
+//! trampoline, so installing one fails with `RtError::Busy` if the thread uses
+//! `rt_thread.cleanup` or `rt_thread.user_data` for another purpose.
+
+use crate::ffi::{
+    rt_sighandler_t, rt_siginfo_t, rt_signal_install, rt_signal_mask, rt_signal_unmask,
+    rt_signal_wait, rt_sigset_t, RT_SIG_MAX,
+};
+use crate::{
+    thread::{slot::ThreadSlot, ThreadContext},
+    time::Timeout,
+    Result, RtError,
+};
+use core::mem::MaybeUninit;
+use cty::c_int;
+
+/// Rust signal handler, receiving the signal number
+pub type SignalHandler = fn(i32);
+
+/// Disposition of a signal
+#[derive(Copy, Clone)]
+pub enum SigAction {
+    /// Default action of rt-thread
+    Default,
+    /// Ignore the signal
+    Ignore,
+    /// Call a Rust handler
+    Handler(SignalHandler),
+}
+
+/// Run in the context of the thread receiving the signal
+unsafe extern "C" fn signal_entry(signo: c_int) {
+    // Must not allocate a slot here, a thread without one has no Rust handler
+    let handler =
+        ThreadSlot::current_existing().and_then(|slot| slot.signal_handler(signo as usize));
+    if let Some(handler) = handler {
+        handler(signo);
+    }
+}
+
+#[inline]
+fn check_signo(signo: i32) -> Result<usize> {
+    if signo >= 0 && signo < RT_SIG_MAX as i32 {
+        Ok(signo as usize)
+    } else {
+        Err(RtError::Inval)
+    }
+}
+
+/// Set of signal numbers used by [wait](wait)
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SigSet(rt_sigset_t);
+
+impl SigSet {
+    pub const fn empty() -> Self {
+        SigSet(0)
+    }
+
+    #[inline]
+    pub fn add(mut self, signo: i32) -> Result<Self> {
+        self.0 |= 1 << check_signo(signo)?;
+        Ok(self)
+    }
+
+    #[inline]
+    pub fn contains(&self, signo: i32) -> bool {
+        check_signo(signo).map_or(false, |signo| self.0 & (1 << signo) != 0)
+    }
+}
+
+///
+/// Install the action of a signal for the current thread.
+///
+/// @param signo the signal number
+/// @param action the new action
+///
+/// @return the error code
+///
+pub fn install(signo: i32, action: SigAction) -> Result<()> {
+    let index = check_signo(signo)?;
+    let slot = ThreadSlot::current()?;
+    let (handler, rust_handler): (rt_sighandler_t, _) = match action {
+        SigAction::Default => (None, None),
+        SigAction::Ignore => (unsafe { core::mem::transmute(1usize) }, None),
+        SigAction::Handler(handler) => (Some(signal_entry), Some(handler)),
+    };
+    let old = unsafe { rt_signal_install(signo, handler) };
+    // SIG_ERR
+    if old.map_or(false, |old| old as usize == usize::MAX) {
+        Err(RtError::Error)
+    } else {
+        slot.set_signal_handler(index, rust_handler);
+        Ok(())
+    }
+}
+
+/// Install a Rust handler of a signal for the current thread
+#[inline]
+pub fn install_handler(signo: i32, handler: SignalHandler) -> Result<()> {
+    install(signo, SigAction::Handler(handler))
+}
+
+///
+/// Mask a signal of the current thread, the signal will not be delivered
+/// until it is unmasked.
+///
+/// @param signo the signal number
+///
+#[inline]
+pub fn mask(signo: i32) -> Result<()> {
+    check_signo(signo)?;
+    unsafe { rt_signal_mask(signo) };
+    Ok(())
+}
+
+///
+/// Unmask a signal of the current thread, a pending signal will be delivered.
+///
+/// @param signo the signal number
+///
+#[inline]
+pub fn unmask(signo: i32) -> Result<()> {
+    check_signo(signo)?;
+    unsafe { rt_signal_unmask(signo) };
+    Ok(())
+}
+
+///
+/// Wait for one of the signals in the set.
+///
+/// @param set the signals to wait
+/// @param time the waiting time
+///
+/// @return the received signal number
+///
+pub fn wait(set: SigSet, time: impl Into<Timeout>, _ctx: ThreadContext) -> Result<i32> {
+    let mut info = MaybeUninit::<rt_siginfo_t>::uninit();
+    let err = unsafe { rt_signal_wait(&set.0, info.as_mut_ptr(), time.into().to_raw()) };
+    RtError::from_code_none_then(err, || unsafe { info.assume_init().si_signo })
+}
@@ -0,0 +1,282 @@
+use super::{
+    slot::{thread_entry, ThreadSlot},
+    Thread,
+};
+use crate::{
+    cstr::RtName,
+    ffi::{
+        rt_thread, rt_thread_create, rt_thread_detach, rt_thread_init, RT_ALIGN_SIZE,
+        RT_NAME_MAX, RT_THREAD_PRIORITY_MAX,
+    },
+    Result, RtError,
+};
+use core::mem::{align_of, size_of};
+
+/// Stack size used when [stack_size](Builder::stack_size) is not set
+pub const DEFAULT_STACK_SIZE: u32 = 2048;
+/// Priority used when [priority](Builder::priority) is not set
+pub const DEFAULT_PRIORITY: u8 = (RT_THREAD_PRIORITY_MAX / 2) as u8;
+/// Time slice used when [tick](Builder::tick) is not set
+pub const DEFAULT_TICK: u32 = 10;
+/// Smallest static stack accepted by [stack](Builder::stack), which must hold at least
+/// the context frame saved by the port
+pub const MIN_STACK_SIZE: usize = 256;
+
+/// Thread factory, which can be used in order to configure the properties of a new
+/// thread
+///
+/// ```ignore
+/// let thread = Builder::new()
+///     .name("worker")
+///     .stack_size(1024)
+///     .priority(12)
+///     .spawn(|| println!("hello"))?;
+/// ```
+///
+/// By default the thread object and its stack are allocated from the heap through
+/// `rt_thread_create`. If a static stack is given by [stack](Builder::stack),
+/// `rt_thread_init` is used instead and the thread object is placed at the beginning
+/// of the stack buffer.
+pub struct Builder<'a> {
+    name: &'a str,
+    stack_size: u32,
+    priority: u8,
+    tick: u32,
+    stack: Option<&'static mut [u8]>,
+}
+
+impl<'a> Builder<'a> {
+    pub fn new() -> Self {
+        Self {
+            name: "rust",
+            stack_size: DEFAULT_STACK_SIZE,
+            priority: DEFAULT_PRIORITY,
+            tick: DEFAULT_TICK,
+            stack: None,
+        }
+    }
+
+    /// Name of the thread, which must be shorter than `RT_NAME_MAX`
+    #[inline]
+    pub fn name(mut self, name: &'a str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Size of the stack allocated from the heap, ignored if a static stack is given
+    #[inline]
+    pub fn stack_size(mut self, stack_size: u32) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Priority of the thread, which must be less than `RT_THREAD_PRIORITY_MAX`
+    #[inline]
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Time slice if there are threads of the same priority
+    #[inline]
+    pub fn tick(mut self, tick: u32) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    /// Use a static buffer as the stack and the thread object
+    ///
+    /// Building fails with `RtError::Inval` if less than
+    /// [MIN_STACK_SIZE](MIN_STACK_SIZE) bytes are left for the stack.
+    #[inline]
+    pub fn stack(mut self, stack: &'static mut [u8]) -> Self {
+        self.stack = Some(stack);
+        self
+    }
+
+    #[inline]
+    pub(super) fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.name.len() >= RT_NAME_MAX as usize
+            || self.priority as u32 >= RT_THREAD_PRIORITY_MAX
+        {
+            Err(RtError::Inval)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Create the thread running `entry` without starting it
+    pub fn build<F>(self, entry: F) -> Result<Thread>
+    where
+        F: FnOnce(),
+        F: Send + 'static,
+    {
+        self.validate()?;
+        let name: RtName = self.name.into();
+        let slot = ThreadSlot::with_entry(entry);
+        let parameter: *mut cty::c_void = slot.cast();
+
+        let result = match self.stack {
+            None => {
+                let raw = unsafe {
+                    rt_thread_create(
+                        name.into(),
+                        Some(thread_entry),
+                        parameter,
+                        self.stack_size,
+                        self.priority,
+                        self.tick,
+                    )
+                };
+                if raw.is_null() {
+                    Err(RtError::Error)
+                } else {
+                    Ok(Thread { raw })
+                }
+            }
+            Some(buffer) => Self::init_static(
+                name,
+                parameter,
+                buffer,
+                self.priority,
+                self.tick,
+            ),
+        };
+
+        match result {
+            Ok(thread) => unsafe { ThreadSlot::attach(thread.raw, slot) },
+            Err(_) => unsafe { ThreadSlot::release(slot) },
+        }
+        result
+    }
+
+    fn init_static(
+        name: RtName,
+        parameter: *mut cty::c_void,
+        buffer: &'static mut [u8],
+        priority: u8,
+        tick: u32,
+    ) -> Result<Thread> {
+        let (offset, stack_start, stack_len) =
+            split_stack(buffer.as_ptr() as usize, buffer.len()).ok_or(RtError::Inval)?;
+        let (head, stack) = buffer.split_at_mut(stack_start);
+        let stack = &mut stack[..stack_len];
+        let raw: *mut rt_thread = head[offset..].as_mut_ptr().cast();
+
+        let err = unsafe {
+            rt_thread_init(
+                raw,
+                name.into(),
+                Some(thread_entry),
+                parameter,
+                stack.as_mut_ptr().cast(),
+                stack.len() as u32,
+                priority,
+                tick,
+            )
+        };
+        RtError::from_code_none(err, Thread { raw })
+    }
+
+    /// Create the thread running `entry` and start it
+    pub fn spawn<F>(self, entry: F) -> Result<Thread>
+    where
+        F: FnOnce(),
+        F: Send + 'static,
+    {
+        let is_static = self.stack.is_some();
+        let thread = self.build(entry)?;
+        if let Err(err) = thread.startup() {
+            discard(thread, is_static);
+            return Err(err);
+        }
+        Ok(thread)
+    }
+}
+
+/// Split a static buffer at `base` of `len` bytes into the thread object and the stack
+///
+/// Return the offset of the object, the offset of the stack and its length. The stack
+/// is aligned to `RT_ALIGN_SIZE` and must be at least [MIN_STACK_SIZE](MIN_STACK_SIZE)
+/// bytes.
+fn split_stack(base: usize, len: usize) -> Option<(usize, usize, usize)> {
+    let align = RT_ALIGN_SIZE as usize;
+    let object_align = align_of::<rt_thread>();
+    let offset = (object_align - base % object_align) % object_align;
+    let object_end = base + offset + size_of::<rt_thread>();
+    let stack_start = (object_end + align - 1) / align * align - base;
+    let stack_len = len.saturating_sub(stack_start) / align * align;
+    if stack_len < MIN_STACK_SIZE {
+        None
+    } else {
+        Some((offset, stack_start, stack_len))
+    }
+}
+
+/// Remove a thread built by [Builder](Builder) which failed to start
+///
+/// The closure is dropped before returning rather than by the reaper, as a scoped
+/// thread may borrow from its [Scope](super::Scope).
+fn discard(thread: Thread, is_static: bool) {
+    if let Some(slot) = unsafe { ThreadSlot::existing(thread.raw) } {
+        drop(slot.take_entry());
+    }
+    if is_static {
+        unsafe { rt_thread_detach(thread.raw) };
+    } else {
+        thread.delete().ok();
+    }
+}
+
+impl Default for Builder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validate_name_and_priority() {
+        let longest = "n".repeat(RT_NAME_MAX as usize - 1);
+        assert!(Builder::new().name(&longest).validate().is_ok());
+        let too_long = "n".repeat(RT_NAME_MAX as usize);
+        assert!(Builder::new().name(&too_long).validate().is_err());
+
+        let lowest = (RT_THREAD_PRIORITY_MAX - 1) as u8;
+        assert!(Builder::new().priority(lowest).validate().is_ok());
+        let invalid = RT_THREAD_PRIORITY_MAX as u8;
+        assert!(Builder::new().priority(invalid).validate().is_err());
+    }
+
+    #[test]
+    fn split_stack_aligns_object_and_stack() {
+        let align = RT_ALIGN_SIZE as usize;
+        let len = size_of::<rt_thread>() + MIN_STACK_SIZE + 2 * align_of::<rt_thread>();
+        for base in 0x2000_0000..0x2000_0000 + 2 * align_of::<rt_thread>() {
+            let (offset, stack_start, stack_len) = split_stack(base, len).unwrap();
+            assert_eq!((base + offset) % align_of::<rt_thread>(), 0);
+            assert!(offset + size_of::<rt_thread>() <= stack_start);
+            assert_eq!((base + stack_start) % align, 0);
+            assert_eq!(stack_len % align, 0);
+            assert!(stack_len >= MIN_STACK_SIZE);
+            assert!(stack_start + stack_len <= len);
+            assert!(len - stack_start - stack_len < align);
+        }
+    }
+
+    #[test]
+    fn split_stack_rejects_small_buffers() {
+        let base = 0x2000_0000;
+        let object = size_of::<rt_thread>();
+        assert!(split_stack(base, 0).is_none());
+        assert!(split_stack(base, object).is_none());
+        assert!(split_stack(base, object + MIN_STACK_SIZE - 1).is_none());
+    }
+}
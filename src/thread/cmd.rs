@@ -0,0 +1,128 @@
+use super::Thread;
+use crate::cmd::{Command, RawCommand};
+use crate::{ffi::*, Result, RtError};
+use core::ptr::null_mut;
+
+/// Command can be used only for thread
+pub trait ThreadCommand: Command {
+    type Return;
+    fn exec(self, thread: &Thread) -> Result<Self::Return>;
+}
+
+impl ThreadCommand for RawCommand {
+    type Return = RawCommand;
+
+    fn exec(mut self, thread: &Thread) -> Result<Self::Return> {
+        match thread.control(&mut self) {
+            Ok(_) => Ok(self),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Change the priority of a running thread
+#[derive(Debug)]
+pub struct ChangePriority {
+    priority: u8,
+}
+
+impl ChangePriority {
+    /// Fails with `RtError::Inval` if `priority` is not less than `RT_THREAD_PRIORITY_MAX`
+    pub fn new(priority: u8) -> Result<Self> {
+        if (priority as u32) < RT_THREAD_PRIORITY_MAX {
+            Ok(Self { priority })
+        } else {
+            Err(RtError::Inval)
+        }
+    }
+}
+
+impl Command for ChangePriority {
+    #[inline]
+    fn get_cmd(&self) -> cty::c_int {
+        RT_THREAD_CTRL_CHANGE_PRIORITY as cty::c_int
+    }
+
+    fn get_arg(&mut self) -> *mut cty::c_void {
+        (&mut self.priority as *mut u8).cast()
+    }
+}
+
+impl ThreadCommand for ChangePriority {
+    type Return = ();
+
+    fn exec(mut self, thread: &Thread) -> Result<Self::Return> {
+        thread.control(&mut self)
+    }
+}
+
+/// Delete or detach the thread depending on how it was created
+#[derive(Debug, Default)]
+pub struct Close;
+
+impl Command for Close {
+    #[inline]
+    fn get_cmd(&self) -> cty::c_int {
+        RT_THREAD_CTRL_CLOSE as cty::c_int
+    }
+
+    fn get_arg(&mut self) -> *mut cty::c_void {
+        null_mut()
+    }
+}
+
+impl ThreadCommand for Close {
+    type Return = ();
+
+    fn exec(mut self, thread: &Thread) -> Result<Self::Return> {
+        thread.control(&mut self)
+    }
+}
+
+/// Bind the thread to a CPU, or unbind it with [BindCpu::unbind](BindCpu::unbind)
+#[cfg(feature = "smp")]
+#[derive(Debug)]
+pub struct BindCpu {
+    cpu: usize,
+}
+
+#[cfg(feature = "smp")]
+impl BindCpu {
+    /// Fails with `RtError::Inval` if `cpu` is not less than `RT_CPUS_NR`
+    pub fn new(cpu: u8) -> Result<Self> {
+        if (cpu as u32) < RT_CPUS_NR {
+            Ok(Self { cpu: cpu as usize })
+        } else {
+            Err(RtError::Inval)
+        }
+    }
+
+    /// Allow the thread to run on any CPU
+    pub fn unbind() -> Self {
+        Self {
+            cpu: RT_CPUS_NR as usize,
+        }
+    }
+}
+
+#[cfg(feature = "smp")]
+impl Command for BindCpu {
+    #[inline]
+    fn get_cmd(&self) -> cty::c_int {
+        RT_THREAD_CTRL_BIND_CPU as cty::c_int
+    }
+
+    /// The CPU number is passed as the pointer value
+    fn get_arg(&mut self) -> *mut cty::c_void {
+        self.cpu as *mut cty::c_void
+    }
+}
+
+#[cfg(feature = "smp")]
+impl ThreadCommand for BindCpu {
+    type Return = ();
+
+    fn exec(mut self, thread: &Thread) -> Result<Self::Return> {
+        thread.control(&mut self)
+    }
+}
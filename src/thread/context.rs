@@ -0,0 +1,66 @@
+//! Thread context
+//!
+//! The counterpart of [IsrContext](crate::isr::IsrContext): a
+//! [ThreadContext](ThreadContext) token proves that the code runs in a thread, and the
+//! blocking operations which need neither a handle nor an object passed to them take
+//! it, e.g. [Thread::delay](super::Thread::delay) or the locks of a `static`
+//! [StaticDataMutex](crate::ipc::lock::StaticDataMutex).
+//!
+//! The token is neither `Send` nor `Sync`, so it cannot be captured by a thread or
+//! timer closure, stored in a `static` or in the [IsrSafe](crate::isr::IsrSafe) state
+//! of a hard timer. [ThreadContext::current](ThreadContext::current) fails in
+//! interrupt context, so an [IsrEntry](crate::isr::IsrEntry) cannot obtain one.
+//!
+//! ```ignore
+//! Thread::create_closure(
+//!     "blink",
+//!     || {
+//!         let ctx = ThreadContext::current().unwrap();
+//!         loop {
+//!             led::toggle();
+//!             Thread::sleep(Duration::from_millis(500), ctx).ok();
+//!         }
+//!     },
+//!     512,
+//!     10,
+//!     10,
+//! )?;
+//! ```
+
+use crate::{ffi::rt_thread_self, isr::in_isr, Result, RtError};
+use core::marker::PhantomData;
+
+/// Token proving that the code runs in a thread
+///
+/// The token cannot be sent to another thread nor to an interrupt handler.
+#[derive(Clone, Copy)]
+pub struct ThreadContext {
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl ThreadContext {
+    /// Get a token for the current thread
+    ///
+    /// Fails with `RtError::Error` in interrupt context or before the scheduler
+    /// starts.
+    #[inline]
+    pub fn current() -> Result<Self> {
+        if in_isr() || unsafe { rt_thread_self() }.is_null() {
+            Err(RtError::Error)
+        } else {
+            Ok(unsafe { Self::new() })
+        }
+    }
+
+    /// Create a token without checking the context
+    ///
+    /// ## Safety
+    ///
+    /// Must be called from a thread, outside of any interrupt handler.
+    #[inline]
+    pub unsafe fn new() -> Self {
+        ThreadContext {
+            _not_send: PhantomData,
+        }
+    }
+}
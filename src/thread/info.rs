@@ -0,0 +1,222 @@
+use super::Thread;
+use crate::{
+    cstr::RtName,
+    ffi::{
+        rt_enter_critical, rt_exit_critical, rt_list_t, rt_object, rt_object_class_type,
+        rt_object_get_information, rt_thread, RT_THREAD_CLOSE, RT_THREAD_INIT, RT_THREAD_READY,
+        RT_THREAD_RUNNING, RT_THREAD_STAT_MASK, RT_THREAD_SUSPEND,
+    },
+};
+use core::{fmt, mem::MaybeUninit};
+
+#[cfg(feature = "alloc")]
+use crate::Vec;
+
+/// Byte rt-thread fills the stack with when the thread is initialized
+const STACK_FILL: u8 = b'#';
+
+/// Scheduling state of a thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    Init,
+    Ready,
+    Suspend,
+    Running,
+    Close,
+    Unknown(u8),
+}
+
+impl From<u8> for ThreadState {
+    fn from(stat: u8) -> Self {
+        match (stat as u32) & RT_THREAD_STAT_MASK {
+            RT_THREAD_INIT => ThreadState::Init,
+            RT_THREAD_READY => ThreadState::Ready,
+            RT_THREAD_SUSPEND => ThreadState::Suspend,
+            RT_THREAD_RUNNING => ThreadState::Running,
+            RT_THREAD_CLOSE => ThreadState::Close,
+            other => ThreadState::Unknown(other as u8),
+        }
+    }
+}
+
+impl fmt::Display for ThreadState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThreadState::Init => f.write_str("init"),
+            ThreadState::Ready => f.write_str("ready"),
+            ThreadState::Suspend => f.write_str("suspend"),
+            ThreadState::Running => f.write_str("running"),
+            ThreadState::Close => f.write_str("close"),
+            ThreadState::Unknown(stat) => write!(f, "unknown({})", stat),
+        }
+    }
+}
+
+/// Snapshot of the status of a thread
+#[derive(Debug, Clone)]
+pub struct ThreadInfo {
+    pub name: RtName,
+    pub current_priority: u8,
+    pub init_priority: u8,
+    pub state: ThreadState,
+    /// Time slice of the thread
+    pub init_tick: u32,
+    /// Ticks left in the current time slice
+    pub remaining_tick: u32,
+    pub stack_addr: usize,
+    pub stack_size: usize,
+    /// Maximum stack usage in bytes since the thread was initialized
+    pub stack_max_used: usize,
+}
+
+impl ThreadInfo {
+    /// Read the status of a thread, the scheduler must be locked
+    unsafe fn read(thread: &rt_thread) -> Self {
+        let mut name = RtName::default();
+        for (dst, src) in name.as_mut_array_str().iter_mut().zip(thread.name.iter()) {
+            *dst = *src as u8;
+        }
+        let stack_addr = thread.stack_addr as usize;
+        let stack_size = thread.stack_size as usize;
+        ThreadInfo {
+            name,
+            current_priority: thread.current_priority,
+            init_priority: thread.init_priority,
+            state: thread.stat.into(),
+            init_tick: thread.init_tick as u32,
+            remaining_tick: thread.remaining_tick as u32,
+            stack_addr,
+            stack_size,
+            stack_max_used: stack_size - unused_stack(stack_addr as *const u8, stack_size),
+        }
+    }
+
+    /// Name of the thread without the trailing `\0`
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Stack which has never been used in bytes
+    #[inline]
+    pub fn stack_free(&self) -> usize {
+        self.stack_size - self.stack_max_used
+    }
+}
+
+/// Count the bytes still holding the fill pattern from the far end of the stack
+///
+/// The stack grows from the top address downward, so the untouched bytes are
+/// at the beginning of the stack area.
+unsafe fn unused_stack(stack: *const u8, size: usize) -> usize {
+    (0..size)
+        .take_while(|&i| stack.add(i).read_volatile() == STACK_FILL)
+        .count()
+}
+
+/// Locks the scheduler for as long as it lives
+pub(super) struct CriticalSection;
+
+impl CriticalSection {
+    pub(super) fn enter() -> Self {
+        unsafe { rt_enter_critical() };
+        CriticalSection
+    }
+}
+
+impl Drop for CriticalSection {
+    fn drop(&mut self) {
+        unsafe { rt_exit_critical() };
+    }
+}
+
+/// Call `f` on every thread of the system, the scheduler must be locked
+unsafe fn for_each_raw<F: FnMut(&rt_thread)>(mut f: F) {
+    let information = rt_object_get_information(rt_object_class_type::RT_Object_Class_Thread);
+    if information.is_null() {
+        return;
+    }
+    let offset = {
+        let object = MaybeUninit::<rt_object>::zeroed().assume_init();
+        &object.list as *const rt_list_t as usize - &object as *const rt_object as usize
+    };
+    let head: *mut rt_list_t = &mut (*information).object_list;
+    let mut node = (*head).next;
+    while node != head {
+        let thread = (node as usize - offset) as *const rt_thread;
+        f(&*thread);
+        node = (*node).next;
+    }
+}
+
+impl Thread {
+    /// Take a snapshot of the status of the thread
+    pub fn info(&self) -> ThreadInfo {
+        let _critical = CriticalSection::enter();
+        unsafe { ThreadInfo::read(&*self.raw) }
+    }
+
+    /// Scheduling state of the thread, cheaper than [info](Thread::info)
+    #[inline]
+    pub fn state(&self) -> ThreadState {
+        unsafe { (*self.raw).stat }.into()
+    }
+}
+
+/// Call `f` with the status of every thread of the system
+///
+/// The scheduler is locked while `f` runs, so `f` shall be short and must not block.
+pub fn for_each_info<F: FnMut(&ThreadInfo)>(mut f: F) {
+    let _critical = CriticalSection::enter();
+    unsafe { for_each_raw(|thread| f(&ThreadInfo::read(thread))) }
+}
+
+/// Iterate over snapshots of all the threads of the system, like `list_thread`
+///
+/// The snapshots are taken at once, so the iterator can be used while other threads
+/// are created or deleted.
+#[cfg(feature = "alloc")]
+pub fn infos() -> impl Iterator<Item = ThreadInfo> {
+    let mut count = 0;
+    {
+        let _critical = CriticalSection::enter();
+        unsafe { for_each_raw(|_| count += 1) };
+    }
+    // Threads created in between are skipped, allocating with the scheduler locked
+    // is not allowed.
+    let mut list = Vec::with_capacity(count);
+    {
+        let _critical = CriticalSection::enter();
+        unsafe {
+            for_each_raw(|thread| {
+                if list.len() < list.capacity() {
+                    list.push(ThreadInfo::read(thread));
+                }
+            })
+        };
+    }
+    list.into_iter()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn state_from_stat() {
+        let states = [
+            (RT_THREAD_INIT, ThreadState::Init),
+            (RT_THREAD_READY, ThreadState::Ready),
+            (RT_THREAD_SUSPEND, ThreadState::Suspend),
+            (RT_THREAD_RUNNING, ThreadState::Running),
+            (RT_THREAD_CLOSE, ThreadState::Close),
+        ];
+        for &(stat, state) in states.iter() {
+            assert_eq!(ThreadState::from(stat as u8), state);
+        }
+        // Bits above the state mask are used for signals and ignored
+        let suspended = (RT_THREAD_SUSPEND | (RT_THREAD_STAT_MASK + 1)) as u8;
+        assert_eq!(ThreadState::from(suspended), ThreadState::Suspend);
+        let unknown = RT_THREAD_STAT_MASK as u8;
+        assert_eq!(ThreadState::from(unknown), ThreadState::Unknown(unknown));
+    }
+}
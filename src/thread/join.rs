@@ -0,0 +1,115 @@
+use super::{Builder, Thread};
+use crate::{
+    ipc::{sem::Semaphore, IpcFlag},
+    time::Timeout,
+    Result, RtError,
+};
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+
+/// Result slot shared by a joinable thread and its [JoinHandle](JoinHandle)
+struct Packet<T> {
+    done: Semaphore,
+    result: UnsafeCell<Option<T>>,
+}
+
+// The result is written by the thread before `done` is released and only read
+// by the handle after `done` is taken.
+unsafe impl<T: Send> Send for Packet<T> {}
+unsafe impl<T: Send> Sync for Packet<T> {}
+
+impl<T> Drop for Packet<T> {
+    fn drop(&mut self) {
+        self.done.delete().ok();
+    }
+}
+
+/// An owned permission to join on a thread, dropping it detaches the thread
+pub struct JoinHandle<T> {
+    thread: Thread,
+    packet: Arc<Packet<T>>,
+    joined: bool,
+}
+
+unsafe impl<T: Send> Send for JoinHandle<T> {}
+
+impl<T> JoinHandle<T> {
+    /// Get the underlying thread, which may have already exited
+    #[inline]
+    pub fn thread(&self) -> Thread {
+        self.thread
+    }
+
+    /// Wait for the thread to finish and return its result
+    #[inline]
+    pub fn join(mut self) -> Result<T> {
+        self.join_timeout(Timeout::Forever)
+    }
+
+    /// Wait at most `time` for the thread to finish and return its result
+    ///
+    /// The handle can be joined again after a timeout. Once the result has been
+    /// returned, further joins fail with `RtError::Empty`.
+    pub fn join_timeout(&mut self, time: impl Into<Timeout>) -> Result<T> {
+        if self.joined {
+            return Err(RtError::Empty);
+        }
+        let mut done = self.packet.done;
+        done.take(time)?;
+        self.joined = true;
+        unsafe { (*self.packet.result.get()).take() }.ok_or(RtError::Error)
+    }
+
+    /// Whether the thread has finished, in which case joining returns immediately
+    pub fn is_finished(&self) -> bool {
+        if self.joined {
+            return true;
+        }
+        let mut done = self.packet.done;
+        if done.try_take().is_ok() {
+            done.release().ok();
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<'a> Builder<'a> {
+    /// Create and start a thread whose result can be retrieved through the
+    /// returned [JoinHandle](JoinHandle)
+    pub fn spawn_joinable<F, T>(self, entry: F) -> Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T,
+        F: Send + 'static,
+        T: Send + 'static,
+    {
+        let packet = Arc::new(Packet {
+            done: Semaphore::create(self.get_name(), 0, IpcFlag::Fifo)?,
+            result: UnsafeCell::new(None),
+        });
+        let their_packet = packet.clone();
+        let thread = self.spawn(move || {
+            let result = entry();
+            unsafe { *their_packet.result.get() = Some(result) };
+            let mut done = their_packet.done;
+            done.release().ok();
+        })?;
+        Ok(JoinHandle {
+            thread,
+            packet,
+            joined: false,
+        })
+    }
+}
+
+/// Spawn a joinable thread with the default [Builder](Builder) settings
+#[inline]
+pub fn spawn<F, T>(entry: F) -> Result<JoinHandle<T>>
+where
+    F: FnOnce() -> T,
+    F: Send + 'static,
+    T: Send + 'static,
+{
+    Builder::new().spawn_joinable(entry)
+}
@@ -0,0 +1,76 @@
+use super::slot::ThreadSlot;
+use crate::Result;
+
+/// A thread local storage key which owns its contents, declared by
+/// [thread_local!](crate::thread_local)
+///
+/// The value is initialized lazily on the first access of every thread and stored in
+/// the Rust slot of the thread (`rt_thread.user_data`). It is dropped by the thread
+/// once its closure returns, or from a reaper thread of low priority if the thread is
+/// deleted or panics before, hence the `Send` bound.
+pub struct LocalKey<T: Send + 'static> {
+    #[doc(hidden)]
+    pub __init: fn() -> T,
+}
+
+impl<T: Send + 'static> LocalKey<T> {
+    /// Access the value of the current thread, initializing it if needed
+    ///
+    /// Panics if the current thread has no Rust slot, see [try_with](LocalKey::try_with).
+    #[inline]
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.try_with(f)
+            .expect("cannot access a thread local value outside of a thread")
+    }
+
+    /// Access the value of the current thread, initializing it if needed
+    ///
+    /// Fails if there is no current thread or the thread uses `rt_thread.cleanup` or
+    /// `rt_thread.user_data` for another purpose.
+    pub fn try_with<F, R>(&'static self, f: F) -> Result<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let key = self as *const Self as usize;
+        let value = match ThreadSlot::current()?.get_local::<T>(key) {
+            Some(value) => value,
+            None => {
+                let value = (self.__init)();
+                ThreadSlot::current()?.insert_local(key, value)
+            }
+        };
+        Ok(f(unsafe { &*value }))
+    }
+}
+
+/// Declare a new thread local storage key of type [LocalKey](crate::thread::LocalKey)
+///
+/// ```ignore
+/// thread_local! {
+///     static CONTEXT: RefCell<Option<&'static str>> = RefCell::new(None);
+/// }
+///
+/// CONTEXT.with(|context| *context.borrow_mut() = Some("flash write"));
+/// ```
+#[macro_export]
+macro_rules! thread_local {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        $crate::thread_local!($(#[$attr])* $vis static $name: $t = $init);
+        $crate::thread_local!($($rest)*);
+    };
+
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::thread::LocalKey<$t> = {
+            fn __init() -> $t {
+                $init
+            }
+            $crate::thread::LocalKey { __init }
+        };
+    };
+}
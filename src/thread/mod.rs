@@ -2,26 +2,54 @@
 //! 
 //! Thread scheduling algorithm is a priority-based full preemptive multi-thread scheduling algorithm, that is, except the interrupt handler, the code of the scheduler's locked part, and the code that prohibits the interrupt, other parts of the system can be preempted, including the thread scheduler itself. The system supports 256 thread priorities (can also be changed to a maximum of 32 or 8 thread priority via configuration file; for STM32 default configuration, it is set as 32 thread priorities), 0 priority represents highest priority, and lowest priority is reserved for idle threads; at the same time, it also supports creating multiple threads with the same priority. The same priority threads are scheduled with a time slice rotation scheduling algorithm, so that each thread runs for the same time; in addition, when the scheduler is looking for threads that are at the highest priority thread and ready, the elapsed time is constant. The system does not limit the number of threads, the number of threads is only related to the specific memory of the hardware platform.
 
-//! ### TODO
-//! 1. Thread control
-//! 1. Extract ThreadParameter to reuse
-
 use crate::ffi::{
-    rt_object, rt_thread, rt_thread_create, rt_thread_delay, rt_thread_delete, rt_thread_detach,
-    rt_thread_find, rt_thread_init, rt_thread_mdelay, rt_thread_resume, rt_thread_self,
-    rt_thread_startup, rt_thread_suspend, rt_thread_t, rt_thread_yield, rt_tick_t,
+    rt_object, rt_thread, rt_thread_control, rt_thread_create, rt_thread_delay, rt_thread_delete,
+    rt_thread_detach, rt_thread_find, rt_thread_init, rt_thread_resume, rt_thread_self,
+    rt_thread_startup, rt_thread_suspend, rt_thread_t, rt_thread_yield,
 };
-use crate::{cstr::RtName, object::Object, Result, RtError};
+use crate::{cstr::RtName, object::Object, time::Ticks, Result, RtError};
 
 use core::{
     cell::UnsafeCell, ffi::c_void, marker::PhantomPinned, mem::MaybeUninit, ops::Deref,
-    ptr::NonNull,
+    ptr::NonNull, time::Duration,
 };
 
 #[cfg(feature = "alloc")]
-use crate::callback::{callback_entry, Callback, CallbackParameter};
+use crate::{callback::CallbackParameter, Box};
+#[cfg(feature = "alloc")]
+use slot::{thread_entry, ThreadSlot};
 use FnOnce;
 
+#[cfg(feature = "alloc")]
+mod builder;
+#[cfg(feature = "alloc")]
+pub use builder::*;
+#[cfg(feature = "alloc")]
+mod join;
+#[cfg(feature = "alloc")]
+pub use join::*;
+#[cfg(feature = "alloc")]
+mod scope;
+#[cfg(feature = "alloc")]
+pub use scope::*;
+#[cfg(feature = "alloc")]
+mod local;
+#[cfg(feature = "alloc")]
+pub use local::*;
+#[cfg(feature = "alloc")]
+mod supervisor;
+#[cfg(feature = "alloc")]
+pub use supervisor::*;
+#[cfg(feature = "alloc")]
+pub(crate) mod slot;
+
+mod cmd;
+pub use cmd::*;
+mod context;
+pub use context::*;
+mod info;
+pub use info::*;
+
 pub type ThreadEntry<P> = unsafe extern "C" fn(P);
 
 pub struct ThreadStatic {
@@ -89,8 +117,8 @@ impl ThreadStatic {
     /// @return the operation status, RT_EOK on OK, -RT_ERROR on error
     ///
     /// ### Wanring:
-    /// Rust thread has no cleanup function and is not able to unwind.
-    /// Directly stopping a Rust thread may lead to resoucre leak.
+    /// Rust thread is not able to unwind. Only the destructors registered by
+    /// [at_exit](at_exit) run, other resources owned by the stopped thread leak.
     #[inline]
     pub fn detach(&'static self) -> Result<()> {
         let err = unsafe { rt_thread_detach(self.raw.get().cast()) };
@@ -114,11 +142,9 @@ impl Deref for ThreadStatic {
 
 /// All methods use immutable self since the safety is guaranteed by rt-thread internal
 ///
-/// TODO: thread cleanup and unwind
+/// TODO: thread unwind
 ///
 /// https://blog.rust-lang.org/inside-rust/2020/02/27/ffi-unwind-design-meeting.html
-///
-/// TODO: thread control
 #[derive(Copy, Clone)]
 #[repr(transparent)]
 pub struct Thread {
@@ -173,8 +199,14 @@ impl Thread {
         }
     }
 
+    ///
+    /// Create a thread running a closure.
+    ///
+    /// The closure is dropped without running if the thread is deleted before
+    /// it starts, and the destructors registered by [at_exit](at_exit) run when
+    /// the thread is cleaned up.
+    ///
     #[cfg(feature = "alloc")]
-    #[inline]
     pub fn create_closure<F>(
         name: &str,
         entry: F,
@@ -186,14 +218,25 @@ impl Thread {
         F: FnOnce(),
         F: Send + 'static,
     {
-        Self::create(
+        let slot = ThreadSlot::with_entry(entry);
+        let parameter: *mut c_void = slot.cast();
+        match Self::create(
             name,
-            callback_entry,
-            entry.into_parameter(),
+            thread_entry,
+            parameter.into(),
             stack_size,
             priority,
             tick,
-        )
+        ) {
+            Ok(thread) => {
+                unsafe { ThreadSlot::attach(thread.raw, slot) };
+                Ok(thread)
+            }
+            Err(err) => {
+                unsafe { ThreadSlot::release(slot) };
+                Err(err)
+            }
+        }
     }
 
     ///
@@ -251,9 +294,12 @@ impl Thread {
     ///
     /// @return the operation status, RT_EOK on OK, -RT_ERROR on error
     ///
+    /// The closure of a thread created by [create_closure](Thread::create_closure)
+    /// is dropped if it has not run yet.
+    ///
     /// ### Wanring:
-    /// Rust thread has no cleanup function and is not able to unwind.
-    /// Directly stopping a Rust thread may lead to resoucre leak.
+    /// Rust thread is not able to unwind. Only the destructors registered by
+    /// [at_exit](at_exit) run, other resources owned by the stopped thread leak.
     #[inline]
     pub fn delete(self) -> Result<()> {
         let err = unsafe { rt_thread_delete(self.raw) };
@@ -268,7 +314,7 @@ impl Thread {
     /// @return RT_EOK
     ///
     #[inline]
-    pub fn yield0() -> Result<()> {
+    pub fn yield0(_ctx: ThreadContext) -> Result<()> {
         let err = unsafe { rt_thread_yield() };
         RtError::from_code_none(err, ())
     }
@@ -281,22 +327,28 @@ impl Thread {
     /// @return RT_EOK
     ///
     #[inline]
-    pub fn delay(tick: rt_tick_t) -> Result<()> {
-        let err = unsafe { rt_thread_delay(tick) };
+    pub fn delay(tick: impl Into<Ticks>, _ctx: ThreadContext) -> Result<()> {
+        let err = unsafe { rt_thread_delay(tick.into().get()) };
         RtError::from_code_none(err, ())
     }
 
+    /// Let current thread sleep for at least `duration`, rounded up to the next tick
+    #[inline]
+    pub fn sleep(duration: Duration, ctx: ThreadContext) -> Result<()> {
+        Self::delay(duration, ctx)
+    }
+
     ///
     /// This function will let current thread delay for some milliseconds.
     ///
-    /// @param ms the delay ms time
+    /// @param ms the delay ms time, negative values do not wait
     ///
     /// @return RT_EOK
     ///
+    #[deprecated(note = "use `Thread::sleep` with a `Duration` or `Thread::delay`")]
     #[inline]
-    pub fn mdelay(ms: i32) -> Result<()> {
-        let err = unsafe { rt_thread_mdelay(ms) };
-        RtError::from_code_none(err, ())
+    pub fn mdelay(ms: i32, ctx: ThreadContext) -> Result<()> {
+        Self::sleep(Duration::from_millis(ms.max(0) as u64), ctx)
     }
 
     ///
@@ -308,7 +360,7 @@ impl Thread {
     /// rt_schedule() must be invoked.
     ///
     #[inline]
-    pub fn suspend(&self) -> Result<()> {
+    pub fn suspend(&self, _ctx: ThreadContext) -> Result<()> {
         let err = unsafe { rt_thread_suspend(self.raw) };
         RtError::from_code_none(err, ())
     }
@@ -325,6 +377,109 @@ impl Thread {
         let err = unsafe { rt_thread_resume(self.raw) };
         RtError::from_code_none(err, ())
     }
+
+    ///
+    /// This function will send a signal to the thread.
+    ///
+    /// @param signo the signal number
+    ///
+    /// @return the operation status, RT_EOK on OK, -RT_EINVAL on invalid signal
+    ///
+    #[cfg(feature = "signal")]
+    #[inline]
+    pub fn kill(&self, signo: i32) -> Result<()> {
+        let err = unsafe { crate::ffi::rt_thread_kill(self.raw, signo) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will control thread behaviors according to control command.
+    ///
+    /// @param cmd the control command
+    ///
+    /// @return the operation status, RT_EOK on OK, -RT_ERROR on error
+    ///
+    #[inline]
+    pub fn control<R>(&self, cmd: &mut dyn ThreadCommand<Return = R>) -> Result<()> {
+        let err = unsafe { rt_thread_control(self.raw, cmd.get_cmd(), cmd.get_arg()) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will change the priority of the thread, fails with
+    /// `RtError::Inval` if `priority` is out of range.
+    ///
+    #[inline]
+    pub fn set_priority(&self, priority: u8) -> Result<()> {
+        ChangePriority::new(priority)?.exec(self)
+    }
+
+    ///
+    /// This function will bind the thread to a CPU.
+    ///
+    #[cfg(feature = "smp")]
+    #[inline]
+    pub fn bind_cpu(&self, cpu: u8) -> Result<()> {
+        BindCpu::new(cpu)?.exec(self)
+    }
+
+    ///
+    /// This function will allow the thread to run on any CPU.
+    ///
+    #[cfg(feature = "smp")]
+    #[inline]
+    pub fn unbind_cpu(&self) -> Result<()> {
+        BindCpu::unbind().exec(self)
+    }
+
+    ///
+    /// This function will delete or detach the thread depending on whether it was
+    /// created or initialized.
+    ///
+    /// ### Wanring:
+    /// Rust thread is not able to unwind. Only the destructors registered by
+    /// [at_exit](at_exit) run, other resources owned by the stopped thread leak.
+    #[inline]
+    pub fn close(self) -> Result<()> {
+        Close.exec(&self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Thread {
+    /// Whether the thread has panicked and was suspended by
+    /// [PanicPolicy::SuspendThread](crate::panic::PanicPolicy::SuspendThread)
+    ///
+    /// Only threads created by this crate or using thread locals can be marked.
+    pub fn is_panicked(&self) -> bool {
+        unsafe { ThreadSlot::existing(self.raw) }.map_or(false, |slot| slot.is_panicked())
+    }
+
+    /// Mark the thread as panicked, return whether it was already marked
+    pub(crate) fn mark_panicked(&self) -> bool {
+        unsafe { ThreadSlot::existing(self.raw) }.map_or(false, |slot| slot.mark_panicked())
+    }
+}
+
+///
+/// Register a destructor of the current thread.
+///
+/// Destructors run in reverse order of registration on the thread itself, once its
+/// closure returns. If the thread is deleted, detached or panics before, or was not
+/// created by this crate, they are called after the thread is cleaned up from a reaper
+/// thread of low priority, so they may block but delay the other destructors.
+///
+/// Fails with `RtError::Busy` if the thread uses `rt_thread.cleanup` or
+/// `rt_thread.user_data` for another purpose.
+///
+#[cfg(feature = "alloc")]
+pub fn at_exit<F>(destructor: F) -> Result<()>
+where
+    F: FnOnce(),
+    F: Send + 'static,
+{
+    ThreadSlot::current()?.push_destructor(Box::new(destructor));
+    Ok(())
 }
 
 impl Object for Thread {
@@ -0,0 +1,177 @@
+use super::{info::CriticalSection, Builder, Thread, ThreadContext, ThreadInfo};
+use crate::{
+    ipc::{sem::Semaphore, IpcFlag},
+    time::Timeout,
+    Box, Result, RtError,
+};
+use alloc::sync::Arc;
+use core::{
+    cell::UnsafeCell,
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+
+/// A scope to spawn threads which can borrow data from outside the scope
+///
+/// See [scope](scope).
+pub struct Scope<'scope, 'env: 'scope> {
+    /// Released once by every spawned thread when it finishes
+    done: Semaphore,
+    spawned: AtomicU32,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+unsafe impl Sync for Scope<'_, '_> {}
+
+/// Result slot shared by a scoped thread and its [ScopedJoinHandle](ScopedJoinHandle)
+struct Packet<T> {
+    done: Semaphore,
+    /// Set by the thread once it no longer needs its thread object
+    finished: AtomicBool,
+    result: UnsafeCell<Option<T>>,
+}
+
+// The result is written by the thread before `done` is released and only read
+// by the handle after `done` is taken.
+unsafe impl<T: Send> Send for Packet<T> {}
+unsafe impl<T: Send> Sync for Packet<T> {}
+
+impl<T> Drop for Packet<T> {
+    fn drop(&mut self) {
+        self.done.delete().ok();
+    }
+}
+
+/// A permission to join on a thread spawned in a [Scope](Scope), which cannot
+/// outlive the scope
+///
+/// Dropping the handle does not detach the thread, the scope still waits for it.
+pub struct ScopedJoinHandle<'scope, T> {
+    thread: Thread,
+    packet: Arc<Packet<T>>,
+    scope: PhantomData<&'scope ()>,
+}
+
+impl<T> ScopedJoinHandle<'_, T> {
+    /// Wait for the thread to finish and return its result
+    pub fn join(self) -> Result<T> {
+        let mut done = self.packet.done;
+        done.take(Timeout::Forever)?;
+        unsafe { (*self.packet.result.get()).take() }.ok_or(RtError::Error)
+    }
+
+    /// Take a snapshot of the status of the thread, `None` once it has finished and
+    /// its thread object may be freed
+    pub fn info(&self) -> Option<ThreadInfo> {
+        let _critical = CriticalSection::enter();
+        if self.packet.finished.load(Ordering::Acquire) {
+            None
+        } else {
+            Some(self.thread.info())
+        }
+    }
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Spawn a thread with the default [Builder](Builder) settings
+    #[inline]
+    pub fn spawn<F, T>(&'scope self, entry: F) -> Result<ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        self.spawn_with(Builder::new(), entry)
+    }
+
+    /// Spawn a thread configured by `builder`
+    pub fn spawn_with<F, T>(
+        &'scope self,
+        builder: Builder<'_>,
+        entry: F,
+    ) -> Result<ScopedJoinHandle<'scope, T>>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let packet = Arc::new(Packet {
+            done: Semaphore::create(builder.get_name(), 0, IpcFlag::Fifo)?,
+            finished: AtomicBool::new(false),
+            result: UnsafeCell::new(None),
+        });
+        let their_packet = packet.clone();
+        let mut scope_done = self.done;
+        let closure: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            let result = entry();
+            unsafe { *their_packet.result.get() = Some(result) };
+            their_packet.finished.store(true, Ordering::Release);
+            let mut done = their_packet.done;
+            done.release().ok();
+            // The last reference may hold a result borrowing from the scope
+            drop(their_packet);
+            scope_done.release().ok();
+        });
+        // The scope waits for the thread before any borrowed data goes out of scope
+        let closure: Box<dyn FnOnce() + Send + 'static> =
+            unsafe { core::mem::transmute(closure) };
+
+        self.spawned.fetch_add(1, Ordering::AcqRel);
+        // The closure has been dropped if spawning failed
+        let thread = builder.spawn(closure).map_err(|err| {
+            self.spawned.fetch_sub(1, Ordering::AcqRel);
+            err
+        })?;
+        Ok(ScopedJoinHandle {
+            thread,
+            packet,
+            scope: PhantomData,
+        })
+    }
+
+    /// Block until all the spawned threads have finished, including the ones spawned
+    /// by spawned threads
+    fn wait_all(&self) {
+        let mut done = self.done;
+        let mut finished = 0;
+        while finished < self.spawned.load(Ordering::Acquire) {
+            if done.take(Timeout::Forever).is_ok() {
+                finished += 1;
+            }
+        }
+    }
+}
+
+/// Create a scope for spawning threads which borrow local data
+///
+/// All the threads spawned in the scope are joined before this function returns.
+///
+/// ```ignore
+/// let mut buffer = [0u8; 64];
+/// let total = AtomicU32::new(0);
+/// let ctx = ThreadContext::current()?;
+/// thread::scope(ctx, |s| {
+///     let (left, right) = buffer.split_at_mut(32);
+///     let filled = s.spawn(|| fill(left)).unwrap();
+///     s.spawn(|| fill(right)).unwrap();
+///     s.spawn(|| {
+///         total.fetch_add(1, Ordering::Relaxed);
+///     })
+///     .unwrap();
+///     filled.join().unwrap()
+/// })?;
+/// ```
+pub fn scope<'env, F, T>(_ctx: ThreadContext, f: F) -> Result<T>
+where
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+{
+    let scope = Scope {
+        done: Semaphore::create("scope", 0, IpcFlag::Fifo)?,
+        spawned: AtomicU32::new(0),
+        scope: PhantomData,
+        env: PhantomData,
+    };
+    let result = f(&scope);
+    scope.wait_all();
+    scope.done.delete().ok();
+    Ok(result)
+}
@@ -0,0 +1,390 @@
+//! Per-thread Rust data attached to `rt_thread.user_data`
+//!
+//! Threads created from closures own a [ThreadSlot](ThreadSlot) holding the closure
+//! until it runs. The slot is detached by the `rt_thread.cleanup` hook, which rt-thread
+//! calls from the idle thread once the thread has exited, been deleted or detached.
+//! The hook runs with interrupts disabled and must not block, so it only queues the
+//! slot for a reaper thread of the lowest priority above idle, which frees it.
+//!
+//! The destructors registered by [at_exit](super::at_exit) and the values of
+//! [LocalKey](super::LocalKey) are dropped by the thread itself once its closure
+//! returns. Those of threads which never get there, because they were deleted, panicked
+//! or were not created by this crate, are dropped on the reaper instead, so they must
+//! be `Send`.
+//!
+//! Threads not created by this crate get a slot lazily, as long as they do not use
+//! `cleanup` or `user_data` for another purpose.
+//!
+//! The slot also stores the values of [LocalKey](super::LocalKey) and the Rust signal
+//! handlers of the thread.
+
+use super::{Thread, ThreadContext};
+use crate::{
+    ffi::{rt_thread, rt_thread_t, RT_THREAD_PRIORITY_MAX},
+    ipc::{
+        sem::{Semaphore, SemaphoreStatic},
+        IpcFlag,
+    },
+    time::Timeout,
+    Box, Result, RtError, Vec,
+};
+#[cfg(feature = "signal")]
+use crate::{
+    ffi::{rt_thread_self, RT_SIG_MAX},
+    signal::SignalHandler,
+};
+#[cfg(feature = "signal")]
+use core::sync::atomic::AtomicUsize;
+use core::{
+    cell::UnsafeCell,
+    ptr::null_mut,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, Ordering},
+};
+use cty::c_void;
+
+const REAPER_STACK_SIZE: u32 = 2048;
+const REAPER_PRIORITY: u8 = (RT_THREAD_PRIORITY_MAX - 2) as u8;
+const REAPER_TICK: u32 = 10;
+
+const REAPER_STOPPED: u8 = 0;
+const REAPER_STARTING: u8 = 1;
+const REAPER_RUNNING: u8 = 2;
+
+static REAPER: AtomicU8 = AtomicU8::new(REAPER_STOPPED);
+static REAPER_NOTIFY: SemaphoreStatic = SemaphoreStatic::new();
+/// Slots detached from their threads, linked through `ThreadSlot::next`
+static PENDING: AtomicPtr<ThreadSlot> = AtomicPtr::new(null_mut());
+
+/// Value of a thread local key, boxed and type erased
+struct Local {
+    key: usize,
+    value: *mut (),
+    drop: unsafe fn(*mut ()),
+}
+
+/// Data only accessed by the owning thread, or by the creator before the thread starts
+struct SlotData {
+    entry: Option<Box<dyn FnOnce() + 'static>>,
+    /// Called from the cleanup hook itself, see [set_exit_hook](ThreadSlot::set_exit_hook)
+    exit_hook: Option<Box<dyn Fn() + 'static>>,
+    destructors: Vec<Box<dyn FnOnce() + 'static>>,
+    locals: Vec<Local>,
+}
+
+/// Slots are only shared as `&ThreadSlot`, so that the thread and observers such as
+/// the supervisor never hold aliasing `&mut`. [SlotData](SlotData) is borrowed
+/// mutably for the duration of a single method, which never runs user code.
+pub(crate) struct ThreadSlot {
+    data: UnsafeCell<SlotData>,
+    panicked: AtomicBool,
+    /// Rust signal handlers as `usize`, 0 for none. Read by the signal trampoline,
+    /// which may interrupt any method of the slot.
+    #[cfg(feature = "signal")]
+    signal_handlers: [AtomicUsize; RT_SIG_MAX as usize],
+    /// Next slot in the reaper queue, set once detached from the thread
+    next: AtomicPtr<ThreadSlot>,
+}
+
+impl ThreadSlot {
+    fn alloc(entry: Option<Box<dyn FnOnce() + 'static>>) -> *mut ThreadSlot {
+        start_reaper();
+        Box::into_raw(Box::new(ThreadSlot {
+            data: UnsafeCell::new(SlotData {
+                entry,
+                exit_hook: None,
+                destructors: Vec::new(),
+                locals: Vec::new(),
+            }),
+            panicked: AtomicBool::new(false),
+            // All zeros is no handler for every signal
+            #[cfg(feature = "signal")]
+            signal_handlers: unsafe { core::mem::zeroed() },
+            next: AtomicPtr::new(null_mut()),
+        }))
+    }
+
+    /// Allocate a slot owning `entry`, to be passed as the parameter of
+    /// [thread_entry](thread_entry)
+    pub(crate) fn with_entry<F>(entry: F) -> *mut ThreadSlot
+    where
+        F: FnOnce(),
+        F: Send + 'static,
+    {
+        Self::alloc(Some(Box::new(entry)))
+    }
+
+    /// Release a slot which has not been attached to a thread
+    pub(crate) unsafe fn release(slot: *mut ThreadSlot) {
+        drop(Box::from_raw(slot))
+    }
+
+    /// Attach the slot to the thread and install the cleanup hook
+    pub(crate) unsafe fn attach(thread: rt_thread_t, slot: *mut ThreadSlot) {
+        (*thread).user_data = slot as usize as _;
+        (*thread).cleanup = Some(thread_cleanup);
+    }
+
+    /// Get the slot of the current thread, creating it if needed
+    pub(crate) fn current() -> Result<&'static ThreadSlot> {
+        let thread = Thread::current()?.raw;
+        unsafe { Self::of(thread) }
+    }
+
+    /// Get the slot of the current thread if it has one, without allocating
+    #[cfg(feature = "signal")]
+    pub(crate) fn current_existing() -> Option<&'static ThreadSlot> {
+        let thread = unsafe { rt_thread_self() };
+        if thread.is_null() {
+            None
+        } else {
+            unsafe { Self::existing(thread) }
+        }
+    }
+
+    /// Get the slot of a thread if it has one, without allocating
+    ///
+    /// Unless `thread` is the current thread or has not started, only
+    /// [is_panicked](ThreadSlot::is_panicked) and
+    /// [mark_panicked](ThreadSlot::mark_panicked) may be used on the slot.
+    pub(crate) unsafe fn existing(thread: rt_thread_t) -> Option<&'static ThreadSlot> {
+        let raw: &rt_thread = &*thread;
+        let slot = raw.user_data as usize as *const ThreadSlot;
+        if !slot.is_null() && raw.cleanup == Some(thread_cleanup) {
+            Some(&*slot)
+        } else {
+            None
+        }
+    }
+
+    unsafe fn of(thread: rt_thread_t) -> Result<&'static ThreadSlot> {
+        let raw: &rt_thread = &*thread;
+        let slot = raw.user_data as usize as *const ThreadSlot;
+        if !slot.is_null() {
+            return if raw.cleanup == Some(thread_cleanup) {
+                Ok(&*slot)
+            } else {
+                // user_data is used by someone else
+                Err(RtError::Busy)
+            };
+        }
+        if raw.cleanup.is_some() {
+            return Err(RtError::Busy);
+        }
+        let slot = Self::alloc(None);
+        Self::attach(thread, slot);
+        Ok(&*slot)
+    }
+
+    /// Mutable access to the data, which must not be borrowed elsewhere
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn data(&self) -> &mut SlotData {
+        &mut *self.data.get()
+    }
+
+    /// Take the closure of the thread, to be called without borrowing the slot
+    pub(super) fn take_entry(&self) -> Option<Box<dyn FnOnce() + 'static>> {
+        unsafe { self.data() }.entry.take()
+    }
+
+    /// Register a function to be called when the thread is cleaned up
+    pub(crate) fn push_destructor(&self, destructor: Box<dyn FnOnce() + 'static>) {
+        unsafe { self.data() }.destructors.push(destructor);
+    }
+
+    /// Set a function called by the cleanup hook, before the thread object is freed
+    ///
+    /// It runs with interrupts disabled, so it must neither block nor allocate or free
+    /// memory. It is dropped with the slot by the reaper.
+    pub(crate) fn set_exit_hook(&self, hook: Box<dyn Fn() + 'static>) {
+        unsafe { self.data() }.exit_hook = Some(hook);
+    }
+
+    /// Mark the thread as panicked, return whether it was already marked
+    #[inline]
+    pub(crate) fn mark_panicked(&self) -> bool {
+        self.panicked.swap(true, Ordering::AcqRel)
+    }
+
+    #[inline]
+    pub(crate) fn is_panicked(&self) -> bool {
+        self.panicked.load(Ordering::Acquire)
+    }
+
+    /// Rust handler of a signal, `signo` must be less than `RT_SIG_MAX`
+    #[cfg(feature = "signal")]
+    pub(crate) fn signal_handler(&self, signo: usize) -> Option<SignalHandler> {
+        match self.signal_handlers[signo].load(Ordering::Acquire) {
+            0 => None,
+            handler => Some(unsafe { core::mem::transmute::<usize, SignalHandler>(handler) }),
+        }
+    }
+
+    #[cfg(feature = "signal")]
+    pub(crate) fn set_signal_handler(&self, signo: usize, handler: Option<SignalHandler>) {
+        self.signal_handlers[signo].store(
+            handler.map_or(0, |handler| handler as usize),
+            Ordering::Release,
+        );
+    }
+
+    /// Get the value of a thread local key
+    pub(crate) fn get_local<T>(&self, key: usize) -> Option<*const T> {
+        unsafe { self.data() }
+            .locals
+            .iter()
+            .find(|local| local.key == key)
+            .map(|local| local.value as *const T)
+    }
+
+    /// Store the value of a thread local key, which is dropped with the slot
+    ///
+    /// The value is boxed, so the returned pointer stays valid when other values
+    /// are inserted.
+    pub(crate) fn insert_local<T>(&self, key: usize, value: T) -> *const T {
+        unsafe fn drop_value<T>(value: *mut ()) {
+            drop(Box::from_raw(value as *mut T))
+        }
+        let value = Box::into_raw(Box::new(value));
+        unsafe { self.data() }.locals.push(Local {
+            key,
+            value: value.cast(),
+            drop: drop_value::<T>,
+        });
+        value
+    }
+
+    /// Run the destructors, then drop the thread local values
+    ///
+    /// The data is not borrowed while they run, since they may register destructors
+    /// or access thread local values themselves.
+    fn run_destructors(&self) {
+        loop {
+            // Destructors registered later may depend on earlier ones
+            let destructor = unsafe { self.data() }.destructors.pop();
+            match destructor {
+                Some(destructor) => destructor(),
+                None => break,
+            }
+        }
+        loop {
+            let local = unsafe { self.data() }.locals.pop();
+            match local {
+                Some(local) => unsafe { (local.drop)(local.value) },
+                None => break,
+            }
+        }
+    }
+}
+
+impl Drop for ThreadSlot {
+    fn drop(&mut self) {
+        self.data.get_mut().entry.take();
+        // Left over if the closure did not return
+        self.run_destructors();
+    }
+}
+
+/// Thread entry of threads created from closures, `parameter` is a
+/// [ThreadSlot](ThreadSlot)
+pub(crate) unsafe extern "C" fn thread_entry(parameter: *mut c_void) {
+    let slot: &ThreadSlot = &*parameter.cast();
+    if let Some(entry) = slot.take_entry() {
+        entry();
+    }
+    slot.run_destructors();
+}
+
+/// Called with interrupts disabled, so the slot is only queued for the reaper
+unsafe extern "C" fn thread_cleanup(thread: *mut rt_thread) {
+    #[cfg(all(not(test), feature = "alloc-tracking"))]
+    crate::allocator::tracking::thread_exited(thread as usize);
+    let slot = (*thread).user_data as usize as *mut ThreadSlot;
+    (*thread).user_data = 0 as _;
+    if slot.is_null() {
+        return;
+    }
+    if let Some(hook) = &(*slot).data().exit_hook {
+        hook();
+    }
+    let mut head = PENDING.load(Ordering::Relaxed);
+    loop {
+        (*slot).next.store(head, Ordering::Relaxed);
+        match PENDING.compare_exchange_weak(head, slot, Ordering::Release, Ordering::Relaxed) {
+            Ok(_) => break,
+            Err(current) => head = current,
+        }
+    }
+    // Slots queued while the reaper is starting are dropped once it runs
+    if REAPER.load(Ordering::Acquire) == REAPER_RUNNING {
+        reaper_notify().release().ok();
+    }
+}
+
+/// Start the reaper thread if it is not running, called before a slot is allocated
+///
+/// Failures are ignored and retried on the next call, queued slots are kept until the
+/// reaper runs.
+fn start_reaper() {
+    if REAPER
+        .compare_exchange(
+            REAPER_STOPPED,
+            REAPER_STARTING,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        )
+        .is_err()
+    {
+        return;
+    }
+    if REAPER_NOTIFY.init("reaper", 0, IpcFlag::Fifo).is_err() {
+        REAPER.store(REAPER_STOPPED, Ordering::Release);
+        return;
+    }
+    let parameter: *mut c_void = null_mut();
+    let started = Thread::create(
+        "reaper",
+        reaper_entry,
+        parameter.into(),
+        REAPER_STACK_SIZE,
+        REAPER_PRIORITY,
+        REAPER_TICK,
+    )
+    .and_then(|thread| {
+        thread.startup().map_err(|err| {
+            thread.delete().ok();
+            err
+        })
+    });
+    match started {
+        Ok(()) => {
+            REAPER.store(REAPER_RUNNING, Ordering::Release);
+            // The reaper drains the queue when it starts, this only covers the slots
+            // queued after that but before the state was set
+            reaper_notify().release().ok();
+        }
+        Err(_) => {
+            REAPER_NOTIFY.detach().ok();
+            REAPER.store(REAPER_STOPPED, Ordering::Release);
+        }
+    }
+}
+
+/// The semaphore waking the reaper, only used by threads and by the cleanup hook,
+/// which runs in the idle thread
+fn reaper_notify() -> Semaphore {
+    REAPER_NOTIFY.get(unsafe { ThreadContext::new() })
+}
+
+/// Drop the queued slots, running their destructors in thread context
+unsafe extern "C" fn reaper_entry(_parameter: *mut c_void) {
+    let mut notify = reaper_notify();
+    loop {
+        let mut slot = PENDING.swap(null_mut(), Ordering::Acquire);
+        while !slot.is_null() {
+            let next = (*slot).next.load(Ordering::Relaxed);
+            drop(Box::from_raw(slot));
+            slot = next;
+        }
+        notify.take(Timeout::Forever).ok();
+    }
+}
@@ -0,0 +1,578 @@
+//! Supervisor restarting worker threads when they fail
+//!
+//! A [Supervisor](Supervisor) owns the [ChildSpec](ChildSpec) of every worker and
+//! monitors the workers from a thread of its own. A worker is noticed as stopped
+//! when the `rt_thread.cleanup` hook runs, and as failed when it has panicked and
+//! been suspended by [PanicPolicy::SuspendThread](crate::panic::PanicPolicy),
+//! in which case the supervisor deletes it.
+//!
+//! ```ignore
+//! panic::set_policy(PanicPolicy::SuspendThread);
+//! let supervisor = Supervisor::new(Strategy::OneForOne)
+//!     .backoff(Backoff::new(10, 1000))
+//!     .child(ChildSpec::new("sensor", || || read_sensor())?.priority(12))
+//!     .child(ChildSpec::new("report", || || report())?.stack_size(4096))
+//!     .start(Builder::new().name("sup").priority(8))?;
+//! let restarts = supervisor.restarts("sensor");
+//! ```
+//!
+//! Workers are stopped with `rt_thread_delete`. Rust threads are not able to unwind,
+//! so only the destructors registered by [at_exit](super::at_exit) run and other
+//! resources owned by a stopped worker leak.
+
+use super::{info::CriticalSection, slot::ThreadSlot, Builder, Thread, ThreadState};
+use crate::{
+    cstr::RtName,
+    ffi::RT_NAME_MAX,
+    ipc::{sem::Semaphore, IpcFlag},
+    time::{Instant, Ticks},
+    Box, Result, RtError, Vec,
+};
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Ticks between two checks for panicked workers
+pub const DEFAULT_POLL_INTERVAL: u32 = 100;
+
+/// Which workers are restarted when one of them fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Only the failed worker is restarted
+    OneForOne,
+    /// All the workers are stopped and restarted together
+    OneForAll,
+}
+
+/// When a stopped worker is restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Always restarted, even if its entry returned
+    Permanent,
+    /// Restarted only if it failed, i.e. panicked or was deleted
+    Transient,
+    /// Never restarted
+    Temporary,
+}
+
+impl RestartPolicy {
+    /// Whether a worker which stopped on its own is restarted
+    fn restarts(self, failed: bool) -> bool {
+        match self {
+            RestartPolicy::Permanent => true,
+            RestartPolicy::Transient => failed,
+            RestartPolicy::Temporary => false,
+        }
+    }
+}
+
+/// Delay before restarting a worker, doubled on every consecutive restart
+///
+/// The count of consecutive restarts is reset once the worker has run for longer
+/// than the maximum delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    initial: u32,
+    max: u32,
+}
+
+impl Backoff {
+    /// Start with `initial` ticks and wait at most `max` ticks
+    pub const fn new(initial: u32, max: u32) -> Self {
+        Backoff { initial, max }
+    }
+
+    /// Restart immediately
+    pub const fn none() -> Self {
+        Backoff { initial: 0, max: 0 }
+    }
+
+    fn delay(&self, consecutive: u32) -> Ticks {
+        let factor = 1u32.checked_shl(consecutive).unwrap_or(u32::MAX);
+        Ticks::new(self.initial.saturating_mul(factor).min(self.max))
+    }
+}
+
+type Factory = Box<dyn Fn() -> Box<dyn FnOnce() + Send + 'static> + Send + 'static>;
+
+/// Specification of a worker thread
+///
+/// `factory` is called every time the worker is started and returns the entry of
+/// the new thread. The name identifies the worker in the
+/// [SupervisorHandle](SupervisorHandle).
+pub struct ChildSpec {
+    name: RtName,
+    stack_size: u32,
+    priority: u8,
+    tick: u32,
+    restart: RestartPolicy,
+    factory: Factory,
+}
+
+impl ChildSpec {
+    /// Fails with `RtError::Inval` if `name` is not shorter than `RT_NAME_MAX`, like
+    /// [Builder::name](Builder::name)
+    pub fn new<M, F>(name: &str, factory: M) -> Result<Self>
+    where
+        M: Fn() -> F + Send + 'static,
+        F: FnOnce() + Send + 'static,
+    {
+        if name.len() >= RT_NAME_MAX as usize {
+            return Err(RtError::Inval);
+        }
+        Ok(ChildSpec {
+            name: name.into(),
+            stack_size: super::DEFAULT_STACK_SIZE,
+            priority: super::DEFAULT_PRIORITY,
+            tick: super::DEFAULT_TICK,
+            restart: RestartPolicy::Permanent,
+            factory: Box::new(move || Box::new(factory())),
+        })
+    }
+
+    #[inline]
+    pub fn stack_size(mut self, stack_size: u32) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    #[inline]
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[inline]
+    pub fn tick(mut self, tick: u32) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    /// Set when the worker is restarted, [Permanent](RestartPolicy::Permanent) by
+    /// default
+    #[inline]
+    pub fn restart(mut self, restart: RestartPolicy) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    fn builder(&self) -> Builder<'_> {
+        Builder::new()
+            .name(self.name.as_str())
+            .stack_size(self.stack_size)
+            .priority(self.priority)
+            .tick(self.tick)
+    }
+}
+
+/// Restart counters of a worker
+#[derive(Debug, Clone)]
+pub struct ChildStats {
+    pub name: RtName,
+    /// Times the worker has been restarted
+    pub restarts: u32,
+    pub running: bool,
+}
+
+struct Counter {
+    name: RtName,
+    restarts: AtomicU32,
+    running: AtomicBool,
+}
+
+/// State shared by the supervisor thread, its handles and the cleanup hooks of the
+/// workers
+struct Shared {
+    /// Released by the cleanup hook of every worker
+    notify: Semaphore,
+    counters: Vec<Counter>,
+    total: AtomicU32,
+}
+
+// The semaphore is only released by the workers and taken by the supervisor thread,
+// the counters are atomic.
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        self.notify.delete().ok();
+    }
+}
+
+/// Lifetime of one incarnation of a worker
+#[derive(Default)]
+struct Life {
+    /// Set by the cleanup hook, the thread object is freed right after
+    exited: AtomicBool,
+    /// Set when the entry has returned
+    returned: AtomicBool,
+}
+
+struct Child {
+    spec: ChildSpec,
+    running: Option<(Thread, Arc<Life>)>,
+    /// Stopped by the supervisor, which is not a failure
+    stopping: bool,
+    started_at: Instant,
+    consecutive: u32,
+    restart_at: Option<Instant>,
+}
+
+impl Child {
+    fn start(&mut self, shared: &Arc<Shared>) -> Result<()> {
+        let life = Arc::new(Life::default());
+        let entry = (self.spec.factory)();
+        let on_return = life.clone();
+        let thread = self.spec.builder().build(move || {
+            entry();
+            on_return.returned.store(true, Ordering::Release);
+        })?;
+
+        let on_exit = life.clone();
+        let shared = shared.clone();
+        match unsafe { ThreadSlot::existing(thread.raw) } {
+            Some(slot) => slot.set_exit_hook(Box::new(move || {
+                on_exit.exited.store(true, Ordering::Release);
+                let mut notify = shared.notify;
+                notify.release().ok();
+            })),
+            None => {
+                thread.delete().ok();
+                return Err(RtError::Error);
+            }
+        }
+        if let Err(err) = thread.startup() {
+            thread.delete().ok();
+            return Err(err);
+        }
+
+        self.running = Some((thread, life));
+        self.stopping = false;
+        self.started_at = Instant::now();
+        Ok(())
+    }
+
+    /// Delete the worker, its exit is noticed once the cleanup hook has run
+    fn kill(&self) {
+        if let Some((thread, life)) = &self.running {
+            // The idle thread frees exited threads, it cannot run meanwhile
+            let _critical = CriticalSection::enter();
+            if !life.exited.load(Ordering::Acquire) && thread.state() != ThreadState::Close {
+                thread.delete().ok();
+            }
+        }
+    }
+
+    /// Whether the worker has panicked and been suspended
+    fn has_panicked(&self) -> bool {
+        match &self.running {
+            Some((thread, life)) => {
+                let _critical = CriticalSection::enter();
+                !life.exited.load(Ordering::Acquire)
+                    && thread.is_panicked()
+                    && thread.state() == ThreadState::Suspend
+            }
+            None => false,
+        }
+    }
+
+    /// Take the state of the worker if it has exited, return whether it failed
+    fn reap(&mut self) -> Option<bool> {
+        let exited = match &self.running {
+            Some((_, life)) => life.exited.load(Ordering::Acquire),
+            None => false,
+        };
+        if !exited {
+            return None;
+        }
+        let (_, life) = self.running.take()?;
+        let returned = life.returned.load(Ordering::Acquire);
+        Some(!self.stopping && !returned)
+    }
+
+    fn should_restart(&self, failed: bool) -> bool {
+        self.spec.restart.restarts(failed)
+    }
+
+    /// Delay before the next restart, counting consecutive restarts
+    fn next_delay(&mut self, backoff: &Backoff, now: Instant) -> Ticks {
+        if now.ticks_since(self.started_at).get() > backoff.max {
+            self.consecutive = 0;
+        }
+        let delay = backoff.delay(self.consecutive);
+        self.consecutive = self.consecutive.saturating_add(1);
+        delay
+    }
+}
+
+/// Builder of a supervisor
+pub struct Supervisor {
+    strategy: Strategy,
+    backoff: Backoff,
+    poll_interval: u32,
+    children: Vec<ChildSpec>,
+}
+
+impl Supervisor {
+    pub fn new(strategy: Strategy) -> Self {
+        Supervisor {
+            strategy,
+            backoff: Backoff::none(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            children: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Ticks between two checks for panicked workers
+    ///
+    /// Exited workers are noticed immediately.
+    #[inline]
+    pub fn poll_interval(mut self, ticks: u32) -> Self {
+        self.poll_interval = ticks.max(1);
+        self
+    }
+
+    /// Add a worker, workers are started in the order they are added
+    #[inline]
+    pub fn child(mut self, spec: ChildSpec) -> Self {
+        self.children.push(spec);
+        self
+    }
+
+    /// Start all the workers and the supervisor thread configured by `builder`
+    ///
+    /// If a worker or the supervisor thread fails to start, the workers already
+    /// started are deleted.
+    pub fn start(self, builder: Builder<'_>) -> Result<SupervisorHandle> {
+        let shared = Arc::new(Shared {
+            notify: Semaphore::create(builder.get_name(), 0, IpcFlag::Fifo)?,
+            counters: self
+                .children
+                .iter()
+                .map(|spec| Counter {
+                    name: spec.name.clone(),
+                    restarts: AtomicU32::new(0),
+                    running: AtomicBool::new(false),
+                })
+                .collect(),
+            total: AtomicU32::new(0),
+        });
+
+        let children: Vec<Child> = self
+            .children
+            .into_iter()
+            .map(|spec| Child {
+                spec,
+                running: None,
+                stopping: false,
+                started_at: Instant::now(),
+                consecutive: 0,
+                restart_at: None,
+            })
+            .collect();
+        // Dropping the monitor on error deletes the started workers
+        let mut monitor = Monitor {
+            strategy: self.strategy,
+            backoff: self.backoff,
+            poll_interval: self.poll_interval,
+            children,
+            shared: shared.clone(),
+        };
+        for (child, counter) in monitor.children.iter_mut().zip(shared.counters.iter()) {
+            child.start(&shared)?;
+            counter.running.store(true, Ordering::Release);
+        }
+
+        let thread = builder.build(move || monitor.run())?;
+        if let Err(err) = thread.startup() {
+            thread.delete().ok();
+            return Err(err);
+        }
+        Ok(SupervisorHandle { thread, shared })
+    }
+}
+
+/// State of the supervisor thread
+struct Monitor {
+    strategy: Strategy,
+    backoff: Backoff,
+    poll_interval: u32,
+    children: Vec<Child>,
+    shared: Arc<Shared>,
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        for child in self.children.iter_mut() {
+            child.stopping = true;
+            child.kill();
+        }
+    }
+}
+
+impl Monitor {
+    fn run(&mut self) -> ! {
+        loop {
+            self.check();
+            let time = self.next_wakeup();
+            let mut notify = self.shared.notify;
+            notify.take(time).ok();
+        }
+    }
+
+    fn check(&mut self) {
+        let now = Instant::now();
+        for index in 0..self.children.len() {
+            if self.children[index].has_panicked() {
+                self.children[index].kill();
+            }
+            let failed = match self.children[index].reap() {
+                Some(failed) => failed,
+                None => continue,
+            };
+            self.shared.counters[index]
+                .running
+                .store(false, Ordering::Release);
+            if self.children[index].stopping || !self.children[index].should_restart(failed) {
+                continue;
+            }
+            let delay = self.children[index].next_delay(&self.backoff, now);
+            let restart_at = now + delay;
+            if self.strategy == Strategy::OneForAll {
+                for child in self.children.iter_mut() {
+                    if child.running.is_some() {
+                        child.stopping = true;
+                        child.kill();
+                        if child.spec.restart != RestartPolicy::Temporary {
+                            child.restart_at = Some(restart_at);
+                        }
+                    }
+                }
+            }
+            self.children[index].restart_at = Some(restart_at);
+        }
+
+        // Workers stopped together are restarted together once all of them exited
+        if self.strategy == Strategy::OneForAll
+            && self
+                .children
+                .iter()
+                .any(|child| child.restart_at.is_some() && child.running.is_some())
+        {
+            return;
+        }
+        for (child, counter) in self.children.iter_mut().zip(self.shared.counters.iter()) {
+            match child.restart_at {
+                Some(at) if child.running.is_none() && at <= now => {}
+                _ => continue,
+            }
+            if child.start(&self.shared).is_ok() {
+                child.restart_at = None;
+                counter.restarts.fetch_add(1, Ordering::AcqRel);
+                counter.running.store(true, Ordering::Release);
+                self.shared.total.fetch_add(1, Ordering::AcqRel);
+            } else {
+                // Out of memory most likely, try again later
+                let delay = child.next_delay(&self.backoff, now);
+                child.restart_at = Some(now + delay.max(Ticks::new(1)));
+            }
+        }
+    }
+
+    /// Ticks until the next pending restart or poll
+    fn next_wakeup(&self) -> Ticks {
+        let now = Instant::now();
+        self.children
+            .iter()
+            .filter_map(|child| child.restart_at)
+            .map(|at| at.ticks_since(now).max(Ticks::new(1)))
+            .fold(Ticks::new(self.poll_interval), Ticks::min)
+    }
+}
+
+/// Handle of a running supervisor, used for diagnostics
+///
+/// Dropping the handle does not stop the supervisor.
+#[derive(Clone)]
+pub struct SupervisorHandle {
+    thread: Thread,
+    shared: Arc<Shared>,
+}
+
+impl SupervisorHandle {
+    /// The supervisor thread
+    #[inline]
+    pub fn thread(&self) -> Thread {
+        self.thread
+    }
+
+    /// Times the worker named `name` has been restarted
+    pub fn restarts(&self, name: &str) -> Option<u32> {
+        self.shared
+            .counters
+            .iter()
+            .find(|counter| counter.name.as_str() == name)
+            .map(|counter| counter.restarts.load(Ordering::Acquire))
+    }
+
+    /// Times any worker has been restarted
+    #[inline]
+    pub fn total_restarts(&self) -> u32 {
+        self.shared.total.load(Ordering::Acquire)
+    }
+
+    /// Counters of every worker, in the order they were added
+    pub fn stats(&self) -> impl Iterator<Item = ChildStats> + '_ {
+        self.shared.counters.iter().map(|counter| ChildStats {
+            name: counter.name.clone(),
+            restarts: counter.restarts.load(Ordering::Acquire),
+            running: counter.running.load(Ordering::Acquire),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let backoff = Backoff::new(10, 100);
+        assert_eq!(backoff.delay(0), Ticks::new(10));
+        assert_eq!(backoff.delay(1), Ticks::new(20));
+        assert_eq!(backoff.delay(3), Ticks::new(80));
+        assert_eq!(backoff.delay(4), Ticks::new(100));
+        assert_eq!(backoff.delay(31), Ticks::new(100));
+        assert_eq!(backoff.delay(32), Ticks::new(100));
+        assert_eq!(backoff.delay(u32::MAX), Ticks::new(100));
+        assert_eq!(Backoff::none().delay(5), Ticks::new(0));
+    }
+
+    #[test]
+    fn restart_policy_table() {
+        assert!(RestartPolicy::Permanent.restarts(true));
+        assert!(RestartPolicy::Permanent.restarts(false));
+        assert!(RestartPolicy::Transient.restarts(true));
+        assert!(!RestartPolicy::Transient.restarts(false));
+        assert!(!RestartPolicy::Temporary.restarts(true));
+        assert!(!RestartPolicy::Temporary.restarts(false));
+    }
+
+    #[test]
+    fn child_name_must_fit() {
+        let longest = "n".repeat(RT_NAME_MAX as usize - 1);
+        let spec = ChildSpec::new(&longest, || || ()).ok().unwrap();
+        assert_eq!(spec.name.as_str(), longest);
+        let too_long = "n".repeat(RT_NAME_MAX as usize);
+        assert!(matches!(
+            ChildSpec::new(&too_long, || || ()),
+            Err(RtError::Inval)
+        ));
+    }
+}
@@ -0,0 +1,398 @@
+//! Time measured by the system tick
+//!
+//! [Ticks](Ticks) is a span of system ticks, [Instant](Instant) a point in time read
+//! from `rt_tick_get` and [Timeout](Timeout) how long a blocking call may wait.
+//! Spans convert from `core::time::Duration`, rounding up to the next tick so that
+//! waits are never shorter than requested.
+//!
+//! ```ignore
+//! sem.take(Duration::from_millis(50))?;
+//! sem.take(Timeout::Forever)?;
+//! let start = Instant::now();
+//! work();
+//! println!("took {:?}", start.elapsed().as_duration());
+//! ```
+
+use crate::ffi::{rt_tick_get, rt_tick_t, RT_TICK_PER_SECOND, RT_WAITING_FOREVER};
+use core::{
+    fmt,
+    ops::{Add, AddAssign, Sub, SubAssign},
+    time::Duration,
+};
+
+/// Frequency of the system tick
+pub const TICK_PER_SECOND: u32 = RT_TICK_PER_SECOND;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// A span of system ticks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ticks(rt_tick_t);
+
+impl Ticks {
+    pub const ZERO: Ticks = Ticks(0);
+    pub const MAX: Ticks = Ticks(rt_tick_t::MAX);
+
+    #[inline]
+    pub const fn new(ticks: rt_tick_t) -> Self {
+        Ticks(ticks)
+    }
+
+    #[inline]
+    pub const fn get(self) -> rt_tick_t {
+        self.0
+    }
+
+    /// Same as `rt_tick_from_millisecond` without the FFI call, rounding up
+    pub fn from_millis(ms: u32) -> Self {
+        Duration::from_millis(ms as u64).into()
+    }
+
+    pub fn as_duration(self) -> Duration {
+        let secs = self.0 / TICK_PER_SECOND;
+        let rest = (self.0 % TICK_PER_SECOND) as u64;
+        Duration::new(
+            secs as u64,
+            (rest * NANOS_PER_SEC / TICK_PER_SECOND as u64) as u32,
+        )
+    }
+
+    #[inline]
+    pub fn saturating_sub(self, rhs: Ticks) -> Ticks {
+        Ticks(self.0.saturating_sub(rhs.0))
+    }
+
+    #[inline]
+    pub fn checked_add(self, rhs: Ticks) -> Option<Ticks> {
+        self.0.checked_add(rhs.0).map(Ticks)
+    }
+}
+
+impl From<rt_tick_t> for Ticks {
+    #[inline]
+    fn from(ticks: rt_tick_t) -> Self {
+        Ticks(ticks)
+    }
+}
+
+impl From<Ticks> for rt_tick_t {
+    #[inline]
+    fn from(ticks: Ticks) -> Self {
+        ticks.0
+    }
+}
+
+/// Round up to the next tick, saturating at [Ticks::MAX](Ticks::MAX)
+impl From<Duration> for Ticks {
+    fn from(duration: Duration) -> Self {
+        let tps = TICK_PER_SECOND as u64;
+        let whole = duration.as_secs().saturating_mul(tps);
+        let part = (duration.subsec_nanos() as u64 * tps + NANOS_PER_SEC - 1) / NANOS_PER_SEC;
+        let ticks = whole.saturating_add(part);
+        Ticks(if ticks > rt_tick_t::MAX as u64 {
+            rt_tick_t::MAX
+        } else {
+            ticks as rt_tick_t
+        })
+    }
+}
+
+impl From<Ticks> for Duration {
+    #[inline]
+    fn from(ticks: Ticks) -> Self {
+        ticks.as_duration()
+    }
+}
+
+impl Add for Ticks {
+    type Output = Ticks;
+
+    #[inline]
+    fn add(self, rhs: Ticks) -> Ticks {
+        Ticks(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Ticks {
+    #[inline]
+    fn add_assign(&mut self, rhs: Ticks) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Ticks {
+    type Output = Ticks;
+
+    #[inline]
+    fn sub(self, rhs: Ticks) -> Ticks {
+        Ticks(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Ticks {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Ticks) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl fmt::Display for Ticks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ticks", self.0)
+    }
+}
+
+/// A point in time of the system tick
+///
+/// The tick counter wraps around, so instants are only comparable if they are less
+/// than half the range of `rt_tick_t` apart, about 24 days at 1000 ticks per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instant(rt_tick_t);
+
+impl Instant {
+    #[inline]
+    pub fn now() -> Self {
+        Instant(unsafe { rt_tick_get() })
+    }
+
+    /// Instant at which the tick counter reads `ticks`, e.g. a `timeout_tick`
+    #[inline]
+    pub const fn from_ticks(ticks: rt_tick_t) -> Self {
+        Instant(ticks)
+    }
+
+    /// Value of the tick counter at this instant
+    #[inline]
+    pub const fn ticks(self) -> rt_tick_t {
+        self.0
+    }
+
+    /// Ticks from `earlier` to `self`, zero if `earlier` is later than `self`
+    #[inline]
+    pub fn ticks_since(self, earlier: Instant) -> Ticks {
+        self.checked_ticks_since(earlier).unwrap_or(Ticks::ZERO)
+    }
+
+    pub fn checked_ticks_since(self, earlier: Instant) -> Option<Ticks> {
+        let diff = self.0.wrapping_sub(earlier.0);
+        if (diff as i32) < 0 {
+            None
+        } else {
+            Some(Ticks(diff))
+        }
+    }
+
+    #[inline]
+    pub fn duration_since(self, earlier: Instant) -> Duration {
+        self.ticks_since(earlier).as_duration()
+    }
+
+    /// Ticks elapsed since this instant
+    #[inline]
+    pub fn elapsed(self) -> Ticks {
+        Instant::now().ticks_since(self)
+    }
+
+    /// Ticks left until this instant, zero if it has passed
+    #[inline]
+    pub fn remaining(self) -> Ticks {
+        self.ticks_since(Instant::now())
+    }
+
+    #[inline]
+    pub fn has_passed(self) -> bool {
+        self <= Instant::now()
+    }
+}
+
+/// Wrap-safe comparison, see [Instant](Instant)
+impl PartialOrd for Instant {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some((self.0.wrapping_sub(other.0) as i32).cmp(&0))
+    }
+}
+
+impl Add<Ticks> for Instant {
+    type Output = Instant;
+
+    #[inline]
+    fn add(self, rhs: Ticks) -> Instant {
+        Instant(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl AddAssign<Ticks> for Instant {
+    #[inline]
+    fn add_assign(&mut self, rhs: Ticks) {
+        self.0 = self.0.wrapping_add(rhs.0);
+    }
+}
+
+impl Sub<Ticks> for Instant {
+    type Output = Instant;
+
+    #[inline]
+    fn sub(self, rhs: Ticks) -> Instant {
+        Instant(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    #[inline]
+    fn add(self, rhs: Duration) -> Instant {
+        self + Ticks::from(rhs)
+    }
+}
+
+impl Sub for Instant {
+    type Output = Ticks;
+
+    #[inline]
+    fn sub(self, rhs: Instant) -> Ticks {
+        self.ticks_since(rhs)
+    }
+}
+
+/// How long a blocking call waits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timeout {
+    /// Wait until the call succeeds, `RT_WAITING_FOREVER`
+    Forever,
+    /// Fail immediately if the call would block, `RT_WAITING_NO`
+    NoWait,
+    /// Wait at most the duration, rounded up to the next tick
+    After(Duration),
+}
+
+impl Timeout {
+    /// Timeout of `ticks` system ticks
+    #[inline]
+    pub fn ticks(ticks: impl Into<Ticks>) -> Self {
+        Timeout::After(ticks.into().as_duration())
+    }
+
+    /// Timeout ending at `deadline`, `NoWait` if it has passed
+    pub fn until(deadline: Instant) -> Self {
+        match deadline.remaining() {
+            Ticks::ZERO => Timeout::NoWait,
+            left => Timeout::ticks(left),
+        }
+    }
+
+    /// Value passed to rt-thread
+    ///
+    /// Durations longer than `i32::MAX` ticks wait forever.
+    pub fn to_raw(self) -> i32 {
+        match self {
+            Timeout::Forever => RT_WAITING_FOREVER,
+            Timeout::NoWait => 0,
+            Timeout::After(duration) => {
+                let ticks = Ticks::from(duration).get();
+                if ticks > i32::MAX as rt_tick_t {
+                    RT_WAITING_FOREVER
+                } else {
+                    ticks as i32
+                }
+            }
+        }
+    }
+}
+
+impl From<Duration> for Timeout {
+    #[inline]
+    fn from(duration: Duration) -> Self {
+        Timeout::After(duration)
+    }
+}
+
+impl From<Ticks> for Timeout {
+    #[inline]
+    fn from(ticks: Ticks) -> Self {
+        Timeout::ticks(ticks)
+    }
+}
+
+/// Raw rt-thread timeout, negative waits forever and 0 does not wait
+impl From<i32> for Timeout {
+    fn from(time: i32) -> Self {
+        match time {
+            t if t < 0 => Timeout::Forever,
+            0 => Timeout::NoWait,
+            t => Timeout::ticks(t as rt_tick_t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn instant_compares_across_wrap() {
+        let before = Instant::from_ticks(rt_tick_t::MAX - 5);
+        let after = before + Ticks::new(10);
+        assert_eq!(after.ticks(), 4);
+        assert!(after > before);
+        assert!(before < after);
+        assert_eq!(after - before, Ticks::new(10));
+        assert_eq!(after.checked_ticks_since(before), Some(Ticks::new(10)));
+        assert_eq!(before.checked_ticks_since(after), None);
+        assert_eq!(before.ticks_since(after), Ticks::ZERO);
+        assert_eq!(after - Ticks::new(10), before);
+    }
+
+    #[test]
+    fn duration_rounds_up_to_ticks() {
+        assert_eq!(Ticks::from(Duration::from_secs(0)), Ticks::ZERO);
+        assert_eq!(Ticks::from(Duration::from_nanos(1)), Ticks::new(1));
+        assert_eq!(
+            Ticks::from(Duration::from_secs(1)),
+            Ticks::new(TICK_PER_SECOND)
+        );
+        assert_eq!(
+            Ticks::from(Duration::from_secs(1) + Duration::from_nanos(1)),
+            Ticks::new(TICK_PER_SECOND + 1)
+        );
+        assert_eq!(Ticks::from(Duration::from_secs(u64::MAX)), Ticks::MAX);
+    }
+
+    #[test]
+    fn ticks_round_trip_through_duration() {
+        assert_eq!(
+            Ticks::new(3 * TICK_PER_SECOND).as_duration(),
+            Duration::from_secs(3)
+        );
+        for &ticks in &[0, 1, 7, TICK_PER_SECOND - 1, TICK_PER_SECOND + 1] {
+            assert_eq!(
+                Ticks::from(Ticks::new(ticks).as_duration()),
+                Ticks::new(ticks)
+            );
+        }
+    }
+
+    #[test]
+    fn timeout_maps_to_raw() {
+        assert_eq!(Timeout::Forever.to_raw(), RT_WAITING_FOREVER);
+        assert_eq!(Timeout::NoWait.to_raw(), 0);
+        assert_eq!(Timeout::ticks(7u32).to_raw(), 7);
+        assert_eq!(Timeout::from(-1).to_raw(), RT_WAITING_FOREVER);
+        assert_eq!(Timeout::from(0), Timeout::NoWait);
+        assert_eq!(Timeout::from(5).to_raw(), 5);
+        assert_eq!(Timeout::from(Ticks::new(9)).to_raw(), 9);
+    }
+
+    #[test]
+    fn timeout_overflow_waits_forever() {
+        assert_eq!(Timeout::ticks(i32::MAX as rt_tick_t).to_raw(), i32::MAX);
+        assert_eq!(
+            Timeout::ticks(i32::MAX as rt_tick_t + 1).to_raw(),
+            RT_WAITING_FOREVER
+        );
+        assert_eq!(
+            Timeout::After(Duration::from_secs(u64::MAX)).to_raw(),
+            RT_WAITING_FOREVER
+        );
+    }
+}
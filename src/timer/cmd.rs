@@ -0,0 +1,144 @@
+use super::Timer;
+use crate::cmd::{Command, RawCommand};
+use crate::{ffi::*, time::Ticks, Result, RtError};
+use core::ptr::null_mut;
+
+/// Command can be used only for timer
+pub trait TimerCommand: Command {
+    type Return;
+    fn exec(self, timer: &Timer) -> Result<Self::Return>;
+}
+
+impl TimerCommand for RawCommand {
+    type Return = RawCommand;
+
+    fn exec(mut self, timer: &Timer) -> Result<Self::Return> {
+        match timer.control(&mut self) {
+            Ok(_) => Ok(self),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Change the timeout of the timer, effective from the next start
+#[derive(Debug)]
+pub struct SetTime {
+    time: rt_tick_t,
+}
+
+impl SetTime {
+    /// Fails with `RtError::Inval` if `time` is not less than `RT_TICK_MAX / 2`, which
+    /// `rt_timer_start` would reject
+    pub fn new(time: impl Into<Ticks>) -> Result<Self> {
+        let time = time.into().get();
+        if time < RT_TICK_MAX / 2 {
+            Ok(Self { time })
+        } else {
+            Err(RtError::Inval)
+        }
+    }
+}
+
+impl Command for SetTime {
+    #[inline]
+    fn get_cmd(&self) -> cty::c_int {
+        RT_TIMER_CTRL_SET_TIME as cty::c_int
+    }
+
+    fn get_arg(&mut self) -> *mut cty::c_void {
+        (&mut self.time as *mut rt_tick_t).cast()
+    }
+}
+
+impl TimerCommand for SetTime {
+    type Return = ();
+
+    fn exec(mut self, timer: &Timer) -> Result<Self::Return> {
+        timer.control(&mut self)
+    }
+}
+
+/// Read the timeout of the timer
+#[derive(Debug, Default)]
+pub struct GetTime {
+    time: rt_tick_t,
+}
+
+impl Command for GetTime {
+    #[inline]
+    fn get_cmd(&self) -> cty::c_int {
+        RT_TIMER_CTRL_GET_TIME as cty::c_int
+    }
+
+    fn get_arg(&mut self) -> *mut cty::c_void {
+        (&mut self.time as *mut rt_tick_t).cast()
+    }
+}
+
+impl TimerCommand for GetTime {
+    type Return = Ticks;
+
+    fn exec(mut self, timer: &Timer) -> Result<Self::Return> {
+        timer.control(&mut self).map(|_| Ticks::new(self.time))
+    }
+}
+
+/// Make the timer stop after its next expiry
+#[derive(Debug, Default)]
+pub struct SetOneshot;
+
+impl Command for SetOneshot {
+    #[inline]
+    fn get_cmd(&self) -> cty::c_int {
+        RT_TIMER_CTRL_SET_ONESHOT as cty::c_int
+    }
+
+    fn get_arg(&mut self) -> *mut cty::c_void {
+        null_mut()
+    }
+}
+
+impl TimerCommand for SetOneshot {
+    type Return = ();
+
+    fn exec(mut self, timer: &Timer) -> Result<Self::Return> {
+        timer.control(&mut self)
+    }
+}
+
+/// Make the timer restart after every expiry
+#[derive(Debug, Default)]
+pub struct SetPeriodic;
+
+impl Command for SetPeriodic {
+    #[inline]
+    fn get_cmd(&self) -> cty::c_int {
+        RT_TIMER_CTRL_SET_PERIODIC as cty::c_int
+    }
+
+    fn get_arg(&mut self) -> *mut cty::c_void {
+        null_mut()
+    }
+}
+
+impl TimerCommand for SetPeriodic {
+    type Return = ();
+
+    fn exec(mut self, timer: &Timer) -> Result<Self::Return> {
+        timer.control(&mut self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_time_bounds() {
+        assert_eq!(SetTime::new(Ticks::new(0)).unwrap().time, 0);
+        let max = RT_TICK_MAX / 2 - 1;
+        assert_eq!(SetTime::new(Ticks::new(max)).unwrap().time, max);
+        assert!(SetTime::new(Ticks::new(RT_TICK_MAX / 2)).is_err());
+        assert!(SetTime::new(Ticks::new(RT_TICK_MAX)).is_err());
+    }
+}
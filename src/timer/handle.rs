@@ -0,0 +1,248 @@
+//! Owned and borrowed timers
+//!
+//! [Timer](Timer) is a plain `Copy` handle which has to be deleted manually. A
+//! [TimerHandle](TimerHandle) owns its timer: dropping it stops and deletes the timer
+//! along with its closure. [TimerRef](TimerRef) borrows a timer, so it can be passed
+//! around to start, stop or retune the timer without being able to delete it.
+//!
+//! ```ignore
+//! let sampler = TimerHandle::create_repeat(
+//!     "sample",
+//!     move || adc.trigger(),
+//!     Duration::from_millis(10),
+//!     TimerFlags::PERIODIC | TimerFlags::SOFT_TIMER,
+//! )?;
+//! sampler.start()?;
+//! retune(sampler.by_ref());
+//! // The timer is stopped and deleted here
+//! ```
+
+use super::{Timer, TimerCommand, TimerEntry, TimerFlags, TimerInfo, TimerStatic};
+use crate::{callback::CallbackParameter, ffi::rt_object, object::Object, time::Ticks, Result};
+use core::{marker::PhantomData, mem::ManuallyDrop, ptr::NonNull};
+
+/// A timer which is stopped and deleted with its closure when dropped
+pub struct TimerHandle {
+    timer: Timer,
+}
+
+unsafe impl Send for TimerHandle {}
+
+impl TimerHandle {
+    /// Same as [Timer::create](Timer::create)
+    pub fn create<P>(
+        name: &str,
+        entry: TimerEntry<P>,
+        parameter: CallbackParameter,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Self>
+    where
+        P: Into<CallbackParameter>,
+    {
+        Timer::create(name, entry, parameter, time, flag).map(|timer| TimerHandle { timer })
+    }
+
+    /// Same as [Timer::create_closure](Timer::create_closure)
+    pub fn create_closure<F>(
+        name: &str,
+        entry: F,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Self>
+    where
+        F: FnOnce(),
+        F: Send + 'static,
+    {
+        Timer::create_closure(name, entry, time, flag).map(|timer| TimerHandle { timer })
+    }
+
+    /// Same as [Timer::create_repeat](Timer::create_repeat)
+    pub fn create_repeat<F>(
+        name: &str,
+        entry: F,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Self>
+    where
+        F: FnMut(),
+        F: Send + 'static,
+    {
+        Timer::create_repeat(name, entry, time, flag).map(|timer| TimerHandle { timer })
+    }
+
+    /// Take the ownership of a timer created by `rt_timer_create`
+    ///
+    /// ## Safety
+    ///
+    /// The timer must not be deleted elsewhere.
+    #[inline]
+    pub unsafe fn from_timer(timer: Timer) -> Self {
+        TimerHandle { timer }
+    }
+
+    /// Give up the ownership, the timer keeps running until deleted manually
+    #[inline]
+    pub fn leak(self) -> Timer {
+        ManuallyDrop::new(self).timer
+    }
+
+    /// Borrow the timer
+    #[inline]
+    pub fn by_ref(&self) -> TimerRef<'_> {
+        TimerRef {
+            timer: self.timer,
+            _borrow: PhantomData,
+        }
+    }
+
+    /// Delete the timer and its closure, reporting the error [Drop](Drop) would ignore
+    #[inline]
+    pub fn delete(self) -> Result<()> {
+        self.leak().delete()
+    }
+
+    #[inline]
+    pub fn start(&self) -> Result<()> {
+        self.timer.start()
+    }
+
+    #[inline]
+    pub fn stop(&self) -> Result<()> {
+        self.timer.stop()
+    }
+
+    #[inline]
+    pub fn control<R>(&self, cmd: &mut dyn TimerCommand<Return = R>) -> Result<()> {
+        self.timer.control(cmd)
+    }
+
+    #[inline]
+    pub fn set_time(&self, time: impl Into<Ticks>) -> Result<()> {
+        self.timer.set_time(time)
+    }
+
+    #[inline]
+    pub fn get_time(&self) -> Result<Ticks> {
+        self.timer.get_time()
+    }
+
+    #[inline]
+    pub fn set_oneshot(&self) -> Result<()> {
+        self.timer.set_oneshot()
+    }
+
+    #[inline]
+    pub fn set_periodic(&self) -> Result<()> {
+        self.timer.set_periodic()
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.timer.is_active()
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> Option<Ticks> {
+        self.timer.remaining()
+    }
+
+    #[inline]
+    pub fn info(&self) -> TimerInfo {
+        self.timer.info()
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        // Deleting also removes the timer from the timer list
+        self.timer.delete().ok();
+    }
+}
+
+impl Object for TimerHandle {
+    #[inline]
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        self.timer.get_ptr()
+    }
+}
+
+/// A borrowed timer which cannot be deleted
+#[derive(Clone, Copy)]
+pub struct TimerRef<'a> {
+    timer: Timer,
+    _borrow: PhantomData<&'a Timer>,
+}
+
+unsafe impl Send for TimerRef<'_> {}
+unsafe impl Sync for TimerRef<'_> {}
+
+impl TimerRef<'_> {
+    #[inline]
+    pub fn start(&self) -> Result<()> {
+        self.timer.start()
+    }
+
+    #[inline]
+    pub fn stop(&self) -> Result<()> {
+        self.timer.stop()
+    }
+
+    #[inline]
+    pub fn control<R>(&self, cmd: &mut dyn TimerCommand<Return = R>) -> Result<()> {
+        self.timer.control(cmd)
+    }
+
+    #[inline]
+    pub fn set_time(&self, time: impl Into<Ticks>) -> Result<()> {
+        self.timer.set_time(time)
+    }
+
+    #[inline]
+    pub fn get_time(&self) -> Result<Ticks> {
+        self.timer.get_time()
+    }
+
+    #[inline]
+    pub fn set_oneshot(&self) -> Result<()> {
+        self.timer.set_oneshot()
+    }
+
+    #[inline]
+    pub fn set_periodic(&self) -> Result<()> {
+        self.timer.set_periodic()
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.timer.is_active()
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> Option<Ticks> {
+        self.timer.remaining()
+    }
+
+    #[inline]
+    pub fn info(&self) -> TimerInfo {
+        self.timer.info()
+    }
+}
+
+impl Object for TimerRef<'_> {
+    #[inline]
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        self.timer.get_ptr()
+    }
+}
+
+impl TimerStatic {
+    /// Borrow the static timer, which must have been initialized
+    #[inline]
+    pub fn by_ref(&'static self) -> TimerRef<'static> {
+        TimerRef {
+            timer: self.get_timer(),
+            _borrow: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use super::{Timer, TimerFlags};
+use crate::{
+    cstr::RtName,
+    ffi::{rt_timer, RT_TIMER_FLAG_ACTIVATED},
+    time::{Instant, Ticks},
+};
+
+/// Snapshot of the settings and status of a timer
+#[derive(Debug, Clone)]
+pub struct TimerInfo {
+    pub name: RtName,
+    /// Timeout set at creation or by [set_time](Timer::set_time)
+    pub time: Ticks,
+    pub periodic: bool,
+    pub hard: bool,
+    pub active: bool,
+    /// Ticks until the next expiry if the timer is active
+    pub remaining: Option<Ticks>,
+}
+
+impl TimerInfo {
+    unsafe fn read(timer: &rt_timer) -> Self {
+        let mut name = RtName::default();
+        for (dst, src) in name
+            .as_mut_array_str()
+            .iter_mut()
+            .zip(timer.parent.name.iter())
+        {
+            *dst = *src as u8;
+        }
+        let flags = TimerFlags::from_bits_truncate(timer.parent.flag);
+        let active = timer.parent.flag as u32 & RT_TIMER_FLAG_ACTIVATED != 0;
+        TimerInfo {
+            name,
+            time: Ticks::new(timer.init_tick),
+            periodic: flags.contains(TimerFlags::PERIODIC),
+            hard: !flags.contains(TimerFlags::SOFT_TIMER),
+            active,
+            remaining: if active {
+                Some(Instant::from_ticks(timer.timeout_tick).remaining())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Name of the timer without the trailing `\0`
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl Timer {
+    /// Take a snapshot of the settings and status of the timer
+    ///
+    /// The timer may expire while it is read, so the snapshot is only consistent if
+    /// the timer is stopped.
+    pub fn info(&self) -> TimerInfo {
+        unsafe { TimerInfo::read(&*self.raw) }
+    }
+}
@@ -0,0 +1,92 @@
+use super::{Timer, TimerFlags, TimerHandle, TimerStatic};
+use crate::{
+    isr::{IsrContext, IsrEntry, IsrSafe},
+    time::Ticks,
+    Result, RtError,
+};
+
+impl Timer {
+    /// Create a hard timer calling `entry` with `state` in interrupt context
+    ///
+    /// `entry` cannot capture anything and only gets the [IsrContext](IsrContext)
+    /// token, so blocking calls on ipc objects or threads do not compile, see
+    /// [crate::isr](crate::isr). `state` is dropped by [delete](Timer::delete).
+    /// Soft timers are rejected with `RtError::Inval`, use
+    /// [create_repeat](Timer::create_repeat) instead.
+    ///
+    /// ```ignore
+    /// let timer = Timer::create_isr(
+    ///     "sample",
+    ///     IsrMessageQueue::from(samples),
+    ///     |samples, isr| {
+    ///         samples.send(adc::read(), isr).ok();
+    ///     },
+    ///     Duration::from_millis(1),
+    ///     TimerFlags::PERIODIC | TimerFlags::HARD_TIMER,
+    /// )?;
+    /// ```
+    pub fn create_isr<S>(
+        name: &str,
+        state: S,
+        entry: IsrEntry<S>,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Timer>
+    where
+        S: IsrSafe + Send + 'static,
+    {
+        if flag.contains(TimerFlags::SOFT_TIMER) {
+            return Err(RtError::Inval);
+        }
+        Self::create_repeat_unchecked(name, isr_closure(state, entry), time, flag)
+    }
+}
+
+impl TimerStatic {
+    /// Initialize a static hard timer calling `entry` with `state` in interrupt
+    /// context, see [Timer::create_isr](Timer::create_isr)
+    ///
+    /// `state` is dropped when the timer is detached.
+    pub fn init_isr<S>(
+        &'static self,
+        name: &str,
+        state: S,
+        entry: IsrEntry<S>,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<()>
+    where
+        S: IsrSafe + Send + 'static,
+    {
+        if flag.contains(TimerFlags::SOFT_TIMER) {
+            return Err(RtError::Inval);
+        }
+        self.init_repeat_unchecked(name, isr_closure(state, entry), time, flag);
+        Ok(())
+    }
+}
+
+impl TimerHandle {
+    /// Same as [Timer::create_isr](Timer::create_isr)
+    pub fn create_isr<S>(
+        name: &str,
+        state: S,
+        entry: IsrEntry<S>,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Self>
+    where
+        S: IsrSafe + Send + 'static,
+    {
+        Timer::create_isr(name, state, entry, time, flag)
+            .map(|timer| unsafe { TimerHandle::from_timer(timer) })
+    }
+}
+
+fn isr_closure<S>(mut state: S, entry: IsrEntry<S>) -> impl FnMut() + Send + 'static
+where
+    S: IsrSafe + Send + 'static,
+{
+    // Hard timers expire in the tick interrupt
+    move || entry(&mut state, unsafe { IsrContext::new() })
+}
@@ -0,0 +1,472 @@
+//! Timer refers to triggering an event after a certain specified time from a specified moment. 
+//! 
+//! For example, setting a timer to wake up yourself the next morning. Timer includes hardware timer and software timer
+
+//! ### Closures
+//! [Timer::create_closure](Timer::create_closure) runs a `FnOnce` closure on the first
+//! expiry, later expiries call nothing. Periodic timers take a `FnMut` closure through
+//! [Timer::create_repeat](Timer::create_repeat). Both closures live as long as the
+//! timer and are dropped by [delete](Timer::delete) or [detach](TimerStatic::detach).
+//!
+//! Closures can block, so these constructors only accept soft timers
+//! ([TimerFlags::SOFT_TIMER](TimerFlags::SOFT_TIMER)) and fail with `RtError::Inval`
+//! otherwise. Hard timers run their callback in interrupt context, where blocking calls
+//! are not allowed. [Timer::create_isr](Timer::create_isr) restricts their callback to
+//! the operations of [crate::isr](crate::isr).
+
+use crate::ffi::{
+    rt_object, rt_tick_from_millisecond, rt_tick_t, rt_timer, rt_timer_control, rt_timer_create,
+    rt_timer_delete, rt_timer_detach, rt_timer_init, rt_timer_start, rt_timer_stop, rt_timer_t,
+    RT_TIMER_FLAG_ACTIVATED, RT_TIMER_FLAG_HARD_TIMER, RT_TIMER_FLAG_ONE_SHOT,
+    RT_TIMER_FLAG_PERIODIC, RT_TIMER_FLAG_SOFT_TIMER,
+};
+use crate::{
+    cstr::RtName,
+    object::Object,
+    time::{Instant, Ticks},
+    Result, RtError,
+};
+
+use core::{cell::UnsafeCell, marker::PhantomPinned, mem::MaybeUninit, ptr::NonNull, ops::Deref};
+#[cfg(feature = "alloc")]
+use cty::c_void;
+
+#[cfg(feature = "alloc")]
+use crate::callback::{release_repeat_callback, repeat_entry, CallbackParameter, RepeatCallback};
+use FnOnce;
+
+mod cmd;
+pub use cmd::*;
+mod info;
+pub use info::*;
+#[cfg(feature = "alloc")]
+mod handle;
+#[cfg(feature = "alloc")]
+pub use handle::*;
+#[cfg(feature = "alloc")]
+mod isr;
+
+bitflags! {
+    pub struct TimerFlags: u8 {
+        const ONE_SHOT = RT_TIMER_FLAG_ONE_SHOT as u8;
+        const PERIODIC = RT_TIMER_FLAG_PERIODIC as u8;
+        const HARD_TIMER = RT_TIMER_FLAG_HARD_TIMER as u8;
+        const SOFT_TIMER = RT_TIMER_FLAG_SOFT_TIMER as u8;
+    }
+}
+
+pub type TimerEntry<P> = unsafe extern "C" fn(P);
+
+pub struct TimerStatic {
+    raw: UnsafeCell<MaybeUninit<rt_timer>>,
+    _pinned: PhantomPinned,
+}
+
+unsafe impl Send for TimerStatic {}
+/// Will be removed once find a way to static init
+unsafe impl Sync for TimerStatic {}
+
+impl TimerStatic {
+    pub const fn new() -> Self {
+        TimerStatic {
+            raw: UnsafeCell::new(core::mem::MaybeUninit::uninit()),
+            _pinned: PhantomPinned {},
+        }
+    }
+
+    ///
+    /// This function will initialize a timer, normally this function is used to
+    /// initialize a static timer object.
+    ///
+    /// @param name the name of timer
+    /// @param timeout the timeout function
+    /// @param parameter the parameter of timeout function
+    /// @param time the tick of timer
+    /// @param flag the flag of timer
+    ///
+    pub fn init<P>(
+        &'static self,
+        name: &str,
+        entry: TimerEntry<P>,
+        parameter: CallbackParameter,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) where
+        P: Into<CallbackParameter>,
+    {
+        let name: RtName = name.into();
+        unsafe {
+            rt_timer_init(
+                self.raw.get().cast(),
+                name.into(),
+                Some(core::mem::transmute(entry)),
+                parameter.get_ptr_mut(),
+                time.into().get(),
+                flag.bits(),
+            )
+        }
+    }
+
+    ///
+    /// This function will detach a timer from timer management.
+    ///
+    /// @param timer the static timer object
+    ///
+    /// @return the operation status, RT_EOK on OK; RT_ERROR on error
+    ///
+    /// The closure of a timer initialized by [init_repeat](TimerStatic::init_repeat)
+    /// is dropped.
+    ///
+    /// ### Wanring:
+    /// Rust timer has no cleanup function and is not able to unwind.
+    /// Directly stopping a Rust timer may lead to resoucre leak.
+    #[inline]
+    pub fn detach(&'static self) -> Result<()> {
+        let raw: rt_timer_t = self.raw.get().cast();
+        #[cfg(feature = "alloc")]
+        let closure = unsafe { repeat_closure(raw) };
+        let err = unsafe { rt_timer_detach(raw) };
+        #[cfg(feature = "alloc")]
+        if err == 0 {
+            if let Some(closure) = closure {
+                unsafe { release_repeat_callback(closure) };
+            }
+        }
+        RtError::from_code_none(err, ())
+    }
+
+    /// Initialize a static soft timer calling `entry` on every expiry
+    ///
+    /// `entry` is dropped when the timer is detached. Hard timers are rejected with
+    /// `RtError::Inval`, see [init_isr](TimerStatic::init_isr).
+    #[cfg(feature = "alloc")]
+    pub fn init_repeat<F>(
+        &'static self,
+        name: &str,
+        entry: F,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<()>
+    where
+        F: FnMut(),
+        F: Send + 'static,
+    {
+        check_soft(flag)?;
+        self.init_repeat_unchecked(name, entry, time, flag);
+        Ok(())
+    }
+
+    #[cfg(feature = "alloc")]
+    fn init_repeat_unchecked<F>(
+        &'static self,
+        name: &str,
+        entry: F,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) where
+        F: FnMut(),
+        F: Send + 'static,
+    {
+        self.init(
+            name,
+            repeat_entry,
+            entry.into_repeat_parameter(),
+            time,
+            flag,
+        )
+    }
+
+    pub fn get_timer(&'static self) -> Timer {
+        Timer {
+            raw: self.raw.get().cast(),
+        }
+    }
+}
+
+impl Deref for TimerStatic {
+    type Target = Timer;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { Timer::new(&mut *self.raw.get().cast()) }
+    }
+}
+
+/// All methods use immutable self since the safety is guaranteed by rt-thread internal
+///
+/// `Timer` does not own the timer, see [TimerHandle](TimerHandle) for a timer deleted
+/// when dropped.
+#[derive(Copy, Clone)]
+pub struct Timer {
+    raw: rt_timer_t,
+}
+
+unsafe impl Send for Timer {}
+
+impl Timer {
+    pub fn new(timer: &mut rt_timer) -> &Timer {
+        unsafe { &*(timer as rt_timer_t as *mut Timer) }
+    }
+    ///
+    /// This function will create a timer
+    ///
+    /// @param name the name of timer
+    /// @param timeout the timeout function
+    /// @param parameter the parameter of timeout function
+    /// @param time the tick of timer
+    /// @param flag the flag of timer
+    ///
+    /// @return the created timer object
+    ///
+    pub fn create<P>(
+        name: &str,
+        entry: TimerEntry<P>,
+        parameter: CallbackParameter,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Timer>
+    where
+        P: Into<CallbackParameter>,
+    {
+        let name: RtName = name.into();
+        let result = unsafe {
+            rt_timer_create(
+                name.into(),
+                Some(core::mem::transmute(entry)),
+                parameter.get_ptr_mut(),
+                time.into().get(),
+                flag.bits(),
+            )
+        };
+        if result.is_null() {
+            Err(RtError::Error)
+        } else {
+            Ok(Timer { raw: result })
+        }
+    }
+
+    /// Create a one-shot soft timer calling `entry` once
+    ///
+    /// The timer can be restarted, in which case it expires without calling anything.
+    /// The closure is dropped by [delete](Timer::delete) if it has not run. Periodic
+    /// timers are rejected with `RtError::Inval`, use
+    /// [create_repeat](Timer::create_repeat) instead, and so are hard timers.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub fn create_closure<F>(
+        name: &str,
+        entry: F,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Timer>
+    where
+        F: FnOnce(),
+        F: Send + 'static,
+    {
+        if flag.contains(TimerFlags::PERIODIC) {
+            return Err(RtError::Inval);
+        }
+        let mut entry = Some(entry);
+        Self::create_repeat(
+            name,
+            move || {
+                if let Some(entry) = entry.take() {
+                    entry()
+                }
+            },
+            time,
+            flag,
+        )
+    }
+
+    /// Create a soft timer calling `entry` on every expiry, usually periodic
+    ///
+    /// `entry` lives as long as the timer and is dropped by [delete](Timer::delete).
+    /// A one-shot timer created this way can be restarted. Hard timers are rejected
+    /// with `RtError::Inval`, see [create_isr](Timer::create_isr).
+    ///
+    /// ```ignore
+    /// let mut count = 0;
+    /// let timer = Timer::create_repeat(
+    ///     "blink",
+    ///     move || {
+    ///         count += 1;
+    ///         led.toggle();
+    ///     },
+    ///     Duration::from_millis(500),
+    ///     TimerFlags::PERIODIC | TimerFlags::SOFT_TIMER,
+    /// )?;
+    /// timer.start()?;
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn create_repeat<F>(
+        name: &str,
+        entry: F,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Timer>
+    where
+        F: FnMut(),
+        F: Send + 'static,
+    {
+        check_soft(flag)?;
+        Self::create_repeat_unchecked(name, entry, time, flag)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn create_repeat_unchecked<F>(
+        name: &str,
+        entry: F,
+        time: impl Into<Ticks>,
+        flag: TimerFlags,
+    ) -> Result<Timer>
+    where
+        F: FnMut(),
+        F: Send + 'static,
+    {
+        let closure = entry.into_repeat_parameter().get_ptr_mut();
+        Self::create(name, repeat_entry, closure.into(), time, flag).map_err(|err| {
+            unsafe { release_repeat_callback(closure) };
+            err
+        })
+    }
+
+    ///
+    /// This function will start the timer
+    ///
+    /// @return the operation status, RT_EOK on OK, -RT_ERROR on error
+    ///
+    #[inline]
+    pub fn start(&self) -> Result<()> {
+        let err = unsafe { rt_timer_start(self.raw) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will delete a timer and release timer memory
+    ///
+    /// @return the operation status, RT_EOK on OK; RT_ERROR on error
+    ///
+    /// The closure of a timer created by [create_closure](Timer::create_closure) or
+    /// [create_repeat](Timer::create_repeat) is dropped, after the current call if the
+    /// timer is deleted from its own closure.
+    ///
+    /// ### Wanring:
+    /// Rust timer has no cleanup function and is not able to unwind.
+    /// Directly stopping a Rust timer may lead to resoucre leak.
+    #[inline]
+    pub fn delete(self) -> Result<()> {
+        #[cfg(feature = "alloc")]
+        let closure = unsafe { repeat_closure(self.raw) };
+        let err = unsafe { rt_timer_delete(self.raw) };
+        #[cfg(feature = "alloc")]
+        if err == 0 {
+            if let Some(closure) = closure {
+                unsafe { release_repeat_callback(closure) };
+            }
+        }
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will stop the timer
+    ///
+    /// @return the operation status, RT_EOK on OK, -RT_ERROR on error
+    ///
+    /// ### Wanring:
+    /// Rust timer has no cleanup function and is not able to unwind.
+    /// Directly stopping a Rust timer may lead to resoucre leak.
+    #[inline]
+    pub fn stop(&self) -> Result<()> {
+        let err = unsafe { rt_timer_stop(self.raw) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will get or set some options of the timer
+    ///
+    /// @param cmd the control command
+    ///
+    /// @return RT_EOK
+    ///
+    #[inline]
+    pub fn control<R>(&self, cmd: &mut dyn TimerCommand<Return = R>) -> Result<()> {
+        let err = unsafe { rt_timer_control(self.raw, cmd.get_cmd(), cmd.get_arg()) };
+        RtError::from_code_none(err, ())
+    }
+
+    ///
+    /// This function will change the timeout of the timer, fails with
+    /// `RtError::Inval` if `time` is out of range.
+    ///
+    /// A running timer keeps its current expiry, the new timeout applies from the
+    /// next start or period.
+    ///
+    #[inline]
+    pub fn set_time(&self, time: impl Into<Ticks>) -> Result<()> {
+        SetTime::new(time)?.exec(self)
+    }
+
+    /// Get the timeout of the timer
+    #[inline]
+    pub fn get_time(&self) -> Result<Ticks> {
+        GetTime::default().exec(self)
+    }
+
+    #[inline]
+    pub fn set_oneshot(&self) -> Result<()> {
+        SetOneshot.exec(self)
+    }
+
+    #[inline]
+    pub fn set_periodic(&self) -> Result<()> {
+        SetPeriodic.exec(self)
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        unsafe { (*self.raw).parent.flag as u32 & RT_TIMER_FLAG_ACTIVATED != 0 }
+    }
+
+    /// Ticks until the next expiry, `None` if the timer is stopped
+    pub fn remaining(&self) -> Option<Ticks> {
+        let timeout = unsafe { (*self.raw).timeout_tick };
+        if self.is_active() {
+            Some(Instant::from_ticks(timeout).remaining())
+        } else {
+            None
+        }
+    }
+}
+
+/// Closures may block, so they only run in the timer thread
+#[cfg(feature = "alloc")]
+fn check_soft(flag: TimerFlags) -> Result<()> {
+    if flag.contains(TimerFlags::SOFT_TIMER) {
+        Ok(())
+    } else {
+        Err(RtError::Inval)
+    }
+}
+
+/// Closure owned by a timer created by [Timer::create_closure](Timer::create_closure)
+/// or [Timer::create_repeat](Timer::create_repeat)
+#[cfg(feature = "alloc")]
+unsafe fn repeat_closure(raw: rt_timer_t) -> Option<*mut c_void> {
+    if (*raw).timeout_func == Some(repeat_entry) {
+        Some((*raw).parameter)
+    } else {
+        None
+    }
+}
+
+impl Object for Timer {
+    #[inline]
+    fn get_ptr(&self) -> NonNull<rt_object> {
+        NonNull::new(self.raw.cast()).expect("Unexpected null rt_timer_t")
+    }
+}
+
+#[inline]
+pub fn from_millisecond(ms: i32) -> rt_tick_t {
+    unsafe { rt_tick_from_millisecond(ms) }
+}